@@ -1,12 +1,16 @@
-use std::io::{self, Write, Read};
+
+use std::io::{self, IsTerminal, Write};
+
+use risp::ToLocated;
 
 fn main() {
     let mut interpreter = risp::Interpreter::new();
-    let mut parser = risp::Parser::new();
+    // each line read is kept so errors can show it
+    let mut sources = risp::SourceMap::new();
 
     loop {
         print!("> ");
-        io::stdout().flush().ok().expect("flush stdout");
+        io::stdout().flush().expect("flush stdout");
 
         let mut line = String::new();
         match io::stdin().read_line(&mut line) {
@@ -19,7 +23,7 @@ fn main() {
                 break;
             },
             Ok(_) => {
-                parse(line, &mut interpreter, &mut parser);
+                eval(&line, &mut interpreter, &mut sources);
             },
 
         }
@@ -27,11 +31,29 @@ fn main() {
 }
 
 
-fn parse(input: String, interpreter: &mut risp::Interpreter, parser: &mut risp::Parser) {
-    match parser.parse(input.as_bytes()) {
-        Ok(exps) => for (i, exp) in exps.iter().enumerate() {
-            println!("${} exp: {:?}", i, exp);
-        },
-        Err(err) => println!("failed to parse: {}", 1),
+fn eval(input: &str, interpreter: &mut risp::Interpreter, sources: &mut risp::SourceMap) {
+    let file = sources.add("<stdin>", input);
+    let options = risp::LexerOptions{file, ..risp::LexerOptions::default()};
+    let processor = risp::Processor::from(risp::Lexer::with_options(input.chars(), options));
+    let colour = match io::stdout().is_terminal() {
+        true => risp::Colour::Ansi,
+        false => risp::Colour::Plain,
+    };
+    for (i, datum) in processor.enumerate() {
+        let span = datum.span;
+        let datum = match datum.data {
+            Ok(datum) => datum.with_span(span),
+            Err(err) => {
+                print!("{}", risp::Diagnostic::from(&err.with_span(span)).render(sources, colour));
+                break;
+            },
+        };
+        match interpreter.eval(&datum) {
+            Ok(value) => println!("${} = {}", i, value),
+            Err(err) => {
+                print!("{}", risp::Diagnostic::from(&err).render(sources, colour));
+                break;
+            },
+        }
     }
 }
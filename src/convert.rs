@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::Hash;
+
+use num_bigint::BigInt;
+
+use super::{Value, Datum, Complex, Real, Arity, RuntimeError};
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+// Conversion of runtime values to Rust data, as used for the parameters of
+// native functions.
+pub trait FromScheme: Sized {
+    // The expected type, reported when the conversion fails.
+    const EXPECTED: &'static str;
+
+    fn from_scheme(value: &Value) -> Option<Self>;
+}
+
+// Conversion of Rust data to runtime values, as used for the results of
+// native functions.
+pub trait IntoScheme {
+    fn into_scheme(self) -> Value;
+}
+
+// Converts `arguments[i]`, reporting the position and expected type otherwise.
+pub fn argument<T: FromScheme>(arguments: &[Value], i: usize) -> Result<T> {
+    match arguments.get(i).and_then(T::from_scheme) {
+        Some(value) => Ok(value),
+        None => error!(RuntimeError::WrongType(i, T::EXPECTED)),
+    }
+}
+
+// A Rust function whose parameters are all `FromScheme` and whose result is
+// `IntoScheme`, callable as a native procedure of fixed arity.
+pub trait IntoNative<Parameters> {
+    fn arity(&self) -> Arity;
+
+    fn call(&self, arguments: &[Value]) -> Result<Value>;
+}
+
+macro_rules! into_native {
+    ($count:expr $(, $parameter:ident $i:tt)*) => {
+        impl<F, R, $($parameter),*> IntoNative<($($parameter,)*)> for F
+        where
+            F: Fn($($parameter),*) -> Result<R>,
+            R: IntoScheme,
+            $($parameter: FromScheme,)*
+        {
+            fn arity(&self) -> Arity {
+                Arity::Exactly($count)
+            }
+
+            #[allow(unused_variables)]
+            fn call(&self, arguments: &[Value]) -> Result<Value> {
+                self($(argument::<$parameter>(arguments, $i)?),*).map(IntoScheme::into_scheme)
+            }
+        }
+    };
+}
+
+into_native!(0);
+into_native!(1, A 0);
+into_native!(2, A 0, B 1);
+into_native!(3, A 0, B 1, C 2);
+into_native!(4, A 0, B 1, C 2, D 3);
+into_native!(5, A 0, B 1, C 2, D 3, E 4);
+into_native!(6, A 0, B 1, C 2, D 3, E 4, G 5);
+
+impl FromScheme for Value {
+    const EXPECTED: &'static str = "value";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl IntoScheme for Value {
+    fn into_scheme(self) -> Value {
+        self
+    }
+}
+
+impl IntoScheme for () {
+    fn into_scheme(self) -> Value {
+        Value::Unspecified
+    }
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {
+        $(
+            impl FromScheme for $t {
+                const EXPECTED: &'static str = concat!("exact integer (", stringify!($t), ")");
+
+                fn from_scheme(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Number(Complex::Real(Real::Integer(i))) => <$t>::try_from(*i).ok(),
+                        Value::Number(Complex::Real(Real::BigInteger(i))) => <$t>::try_from(&**i).ok(),
+                        _ => None,
+                    }
+                }
+            }
+
+            impl IntoScheme for $t {
+                fn into_scheme(self) -> Value {
+                    match i64::try_from(self) {
+                        Ok(i) => Value::Number(Complex::Real(Real::Integer(i))),
+                        Err(_) => Value::Number(Complex::Real(Real::BigInteger(Box::new(BigInt::from(self))))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromScheme for f64 {
+    const EXPECTED: &'static str = "real number";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(Complex::Real(real)) => Some(real.to_f64()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoScheme for f64 {
+    fn into_scheme(self) -> Value {
+        Value::Number(Complex::Real(Real::float(self)))
+    }
+}
+
+impl FromScheme for bool {
+    const EXPECTED: &'static str = "boolean";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        match value {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl IntoScheme for bool {
+    fn into_scheme(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl FromScheme for char {
+    const EXPECTED: &'static str = "character";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        match value {
+            Value::Character(c) => Some(*c),
+            _ => None,
+        }
+    }
+}
+
+impl IntoScheme for char {
+    fn into_scheme(self) -> Value {
+        Value::Character(self)
+    }
+}
+
+impl FromScheme for String {
+    const EXPECTED: &'static str = "string";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(s.borrow().clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoScheme for String {
+    fn into_scheme(self) -> Value {
+        Value::string(&self)
+    }
+}
+
+impl IntoScheme for &str {
+    fn into_scheme(self) -> Value {
+        Value::string(self)
+    }
+}
+
+// Lists and vectors convert to `Vec`, which converts back to a list.
+impl<T: FromScheme> FromScheme for Vec<T> {
+    const EXPECTED: &'static str = "list";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        let elements = match value {
+            Value::Vector(values) => values.borrow().clone(),
+            value => value.list_to_vec()?,
+        };
+        elements.iter().map(T::from_scheme).collect()
+    }
+}
+
+impl<T: IntoScheme> IntoScheme for Vec<T> {
+    fn into_scheme(self) -> Value {
+        Value::list(self.into_iter().map(IntoScheme::into_scheme).collect::<Vec<_>>())
+    }
+}
+
+// `#f` stands for `None`, so an `Option<bool>` is never `Some(false)`: `#f`
+// reads as `None`, and `Some(false)` writes as `#f`.
+impl<T: FromScheme> FromScheme for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        match value {
+            Value::Boolean(false) => Some(None),
+            value => T::from_scheme(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoScheme> IntoScheme for Option<T> {
+    fn into_scheme(self) -> Value {
+        match self {
+            Some(value) => value.into_scheme(),
+            None => Value::Boolean(false),
+        }
+    }
+}
+
+// Tuples convert to and from lists of the same length.
+macro_rules! tuple {
+    ($count:expr, $($t:ident $i:tt),*) => {
+        impl<$($t: FromScheme),*> FromScheme for ($($t,)*) {
+            const EXPECTED: &'static str = concat!("list of ", stringify!($count), " elements");
+
+            fn from_scheme(value: &Value) -> Option<Self> {
+                match value.list_to_vec()?.as_slice() {
+                    [$($t),*] => Some(($($t::from_scheme($t)?,)*)),
+                    _ => None,
+                }
+            }
+        }
+
+        impl<$($t: IntoScheme),*> IntoScheme for ($($t,)*) {
+            fn into_scheme(self) -> Value {
+                Value::list(vec![$(self.$i.into_scheme()),*])
+            }
+        }
+    };
+}
+
+#[allow(non_snake_case)]
+mod tuples {
+    use super::*;
+
+    tuple!(1, A 0);
+    tuple!(2, A 0, B 1);
+    tuple!(3, A 0, B 1, C 2);
+    tuple!(4, A 0, B 1, C 2, D 3);
+}
+
+// Maps convert to and from association lists; as with `assoc`, the first
+// entry for a key wins.
+impl<K: FromScheme + Eq + Hash, V: FromScheme> FromScheme for HashMap<K, V> {
+    const EXPECTED: &'static str = "association list";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        let mut map = HashMap::new();
+        for entry in value.list_to_vec()? {
+            match entry {
+                Value::Pair(pair) => {
+                    let key = K::from_scheme(&pair.car())?;
+                    let value = V::from_scheme(&pair.cdr())?;
+                    map.entry(key).or_insert(value);
+                },
+                _ => return None,
+            }
+        }
+        Some(map)
+    }
+}
+
+impl<K: IntoScheme, V: IntoScheme> IntoScheme for HashMap<K, V> {
+    fn into_scheme(self) -> Value {
+        Value::list(self.into_iter().map(|(k, v)| Value::cons(k.into_scheme(), v.into_scheme())).collect::<Vec<_>>())
+    }
+}
+
+// Only values with an external representation convert to `Datum`.
+impl FromScheme for Datum {
+    const EXPECTED: &'static str = "datum";
+
+    fn from_scheme(value: &Value) -> Option<Self> {
+        value.to_datum()
+    }
+}
+
+impl IntoScheme for Datum {
+    fn into_scheme(self) -> Value {
+        Value::from(&self)
+    }
+}
+
+#[cfg(test)]
+fn round_trip<T: FromScheme + IntoScheme + Clone + PartialEq + std::fmt::Debug>(value: T) {
+    assert_eq!(T::from_scheme(&value.clone().into_scheme()), Some(value));
+}
+
+#[test]
+fn conversions() {
+    round_trip(-3i64);
+    round_trip(200u8);
+    round_trip(1.5f64);
+    round_trip(f64::INFINITY);
+    round_trip(true);
+    round_trip('λ');
+    round_trip("text".to_string());
+    round_trip(vec![1i32, 2, 3]);
+    round_trip(Some(vec!['a']));
+    round_trip(None::<String>);
+    round_trip((1u32, "b".to_string(), false));
+    round_trip([("a".to_string(), 1i64), ("b".to_string(), 2)].iter().cloned().collect::<HashMap<_, _>>());
+    round_trip(Datum::Symbol("s".to_string()));
+
+    let integer = |i: i64| Value::Number(Complex::Real(Real::Integer(i)));
+    round_trip(u64::MAX);
+    assert_eq!(u8::from_scheme(&integer(256)), None);
+    assert_eq!(i64::from_scheme(&Value::Number(Complex::Real(Real::Float(1.0)))), None);
+    assert_eq!(f64::from_scheme(&integer(2)), Some(2.0));
+    assert_eq!(Vec::<i64>::from_scheme(&Value::vector(vec![integer(1)])), Some(vec![1]));
+    assert_eq!(Vec::<i64>::from_scheme(&Value::list(vec![integer(1), Value::Null])), None);
+    assert_eq!(<(i64, i64)>::from_scheme(&Value::list(vec![integer(1)])), None);
+    assert_eq!(Datum::from_scheme(&Value::Eof), None);
+
+    // `#f` is always `None`
+    round_trip(Some(true));
+    assert_eq!(Option::<bool>::from_scheme(&Value::Boolean(false)), Some(None));
+    assert_eq!(Some(false).into_scheme(), Value::Boolean(false));
+
+    let alist = Value::list(vec![
+        Value::cons(Value::string("a"), integer(1)),
+        Value::cons(Value::string("b"), integer(2)),
+        Value::cons(Value::string("a"), integer(3)),
+    ]);
+    let map = HashMap::<String, i64>::from_scheme(&alist).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["a"], 1);
+    assert_eq!(HashMap::<String, i64>::from_scheme(&Value::list(vec![Value::string("a")])), None);
+    assert_eq!("s".into_scheme(), Value::string("s"));
+    assert_eq!(().into_scheme(), Value::Unspecified);
+}
@@ -0,0 +1,245 @@
+use std::fmt::Write as _;
+
+use super::{Located, ToLocated, Span, SourceMap, LexerError, ProcessorError, RuntimeError, Token};
+
+// Whether `Diagnostic::render` colours its output with ANSI escapes.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Colour {
+    Plain,
+    Ansi,
+}
+
+// An error as shown to people: a message about the source at `span`, with
+// more spans to point at, notes and help.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    // shown next to the carets under `span`
+    pub label: Option<String>,
+    // related source, underlined with dashes and labelled
+    pub secondary: Vec<(Span, String)>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: &str, span: Span) -> Self {
+        Self {
+            message: message.to_string(),
+            span,
+            label: None,
+            secondary: vec![],
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, label: &str) -> Self {
+        self.secondary.push((span, label.to_string()));
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    // The diagnostic with the source lines of its spans from `sources`; a
+    // span in a file not there is shown by its line and column alone.
+    pub fn render(&self, sources: &SourceMap, colour: Colour) -> String {
+        let paint = |text: &str, style: &str| match colour {
+            Colour::Plain => text.to_string(),
+            Colour::Ansi => format!("\x1b[{}m{}\x1b[0m", style, text),
+        };
+        let mut snippets = vec![(self.span, self.label.as_deref(), '^', RED)];
+        snippets.extend(self.secondary.iter().map(|(span, label)| (*span, Some(label.as_str()), '-', BLUE)));
+        // the gutter fits the largest line number shown
+        let width = snippets.iter()
+            .filter(|(span, ..)| sources.line(span.start).is_some())
+            .map(|(span, ..)| (span.start.row + 1).to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = paint(&format!("{} |", " ".repeat(width)), BLUE);
+
+        let mut out = format!("{}{}\n", paint("error", RED), paint(&format!(": {}", self.message), BOLD));
+        let start = self.span.start;
+        match sources.resolve(start) {
+            Some(position) => writeln!(out, "{}{} {}", " ".repeat(width), paint("-->", BLUE), position),
+            None => writeln!(out, "{}{} {}:{}", " ".repeat(width), paint("-->", BLUE), start.row + 1, start.col + 1),
+        }.unwrap();
+
+        for (span, label, marker, style) in snippets {
+            let line = match sources.line(span.start) {
+                Some(line) => line,
+                None => continue,
+            };
+            let col = span.start.col as usize;
+            // up to the end of the line for a span that runs past it
+            let length = match span.end.row == span.start.row {
+                true => (span.end.col as usize).saturating_sub(col),
+                false => line.chars().count().saturating_sub(col),
+            };
+            // tabs are kept so the markers line up under them
+            let indent: String = line.chars().take(col).map(|ch| if ch == '\t' {'\t'} else {' '}).collect();
+            let markers = marker.to_string().repeat(length.max(1));
+            let markers = match label {
+                Some(label) => format!("{} {}", markers, label),
+                None => markers,
+            };
+            writeln!(out, "{}", gutter).unwrap();
+            writeln!(out, "{} {}", paint(&format!("{:>width$} |", span.start.row + 1, width = width), BLUE), line).unwrap();
+            writeln!(out, "{} {}{}", gutter, indent, paint(&markers, style)).unwrap();
+        }
+
+        for note in self.notes.iter() {
+            writeln!(out, "{}{} {}", " ".repeat(width + 1), paint("= note:", BOLD), note).unwrap();
+        }
+        if let Some(help) = &self.help {
+            writeln!(out, "{}{} {}", " ".repeat(width + 1), paint("= help:", BOLD), help).unwrap();
+        }
+        out
+    }
+}
+
+const RED: &str = "1;31";
+const BLUE: &str = "1;34";
+const BOLD: &str = "1";
+
+impl From<&Located<LexerError>> for Diagnostic {
+    fn from(error: &Located<LexerError>) -> Self {
+        let diagnostic = Diagnostic::new(&error.data.to_string(), error.span);
+        match error.data {
+            LexerError::UnexpectedEnd => diagnostic.with_label("the input ends before this is complete"),
+            LexerError::ExpectedDelimiter => diagnostic
+                .with_label("expected a delimiter here")
+                .with_help("separate it from what follows with whitespace or a parenthesis"),
+            LexerError::InvalidCharacter => diagnostic
+                .with_help(r"characters are written like #\a, #\space or #\x3bb"),
+            LexerError::InvalidEscape => diagnostic
+                .with_help(r#"the escapes are \a, \b, \t, \n, \r, \", \\, \| and hex escapes like \x3bb;"#),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&Located<ProcessorError>> for Diagnostic {
+    fn from(error: &Located<ProcessorError>) -> Self {
+        let diagnostic = Diagnostic::new(&error.data.to_string(), error.span);
+        match &error.data {
+            ProcessorError::LexerError(e) => Diagnostic::from(&e.clone().with_span(error.span)),
+            ProcessorError::UnmatchedParentheses => diagnostic.with_label("this closes nothing"),
+            // the error is at the start of the unfinished datum
+            ProcessorError::UnexpectedEnd => diagnostic.with_label("unclosed datum opened here"),
+            ProcessorError::UnexpectedToken(Token::Period) => diagnostic
+                .with_label("not expected here")
+                .with_note("a `.` only goes before the last datum of a list"),
+            ProcessorError::UnexpectedToken(_) => diagnostic.with_label("not expected here"),
+            ProcessorError::UndefinedLabel(n) => diagnostic
+                .with_label(&format!("`#{}#` refers to no label", n))
+                .with_note(&format!("a reference has to follow its `#{}=` in the same datum", n)),
+        }
+    }
+}
+
+impl From<&Located<RuntimeError>> for Diagnostic {
+    fn from(error: &Located<RuntimeError>) -> Self {
+        let diagnostic = Diagnostic::new(&error.data.to_string(), error.span);
+        match &error.data {
+            RuntimeError::ProcessorError(e) => Diagnostic::from(&e.clone().with_span(error.span)),
+            RuntimeError::UnboundVariable(_) => diagnostic.with_label("not defined"),
+            RuntimeError::UndefinedAssignment(_) => diagnostic
+                .with_label("not defined")
+                .with_help("use `define` before assigning with `set!`"),
+            RuntimeError::NotProcedure(_) | RuntimeError::WrongArgumentCount(..)
+            | RuntimeError::WrongType(..) | RuntimeError::IndexOutOfRange(_)
+            | RuntimeError::DivisionByZero => diagnostic.with_label("in this call"),
+            RuntimeError::NumberTooLarge => diagnostic
+                .with_label("in this call")
+                .with_help("an inexact argument gives an approximate result"),
+            RuntimeError::RecursionLimitExceeded => diagnostic
+                .with_help("the limit is set by `Interpreter::set_stack_limit`"),
+            _ => diagnostic,
+        }
+    }
+}
+
+#[test]
+fn render() {
+    use super::{Interpreter, Lexer, LexerOptions, Processor};
+
+    let mut sources = SourceMap::new();
+    let file = sources.add("lib/list.scm", "(define xs\n  (list 1\n\t\t2)\n(car\n");
+    let options = LexerOptions{file, ..LexerOptions::default()};
+    let text = sources.get(file).unwrap().text.clone();
+    let Located{data, span} = Processor::from(Lexer::with_options(text.chars(), options)).next().unwrap();
+    let error = data.unwrap_err().with_span(span);
+    assert_eq!(Diagnostic::from(&error).render(&sources, Colour::Plain), concat!(
+        "error: unexpected end of input\n",
+        " --> lib/list.scm:4:1\n",
+        "  |\n",
+        "4 | (car\n",
+        "  | ^ unclosed datum opened here\n",
+    ));
+
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.eval_str("(car '())").unwrap_err();
+    let diagnostic = Diagnostic::from(&error)
+        .with_secondary(Span{start: error.span.start, end: error.span.start}, "called here")
+        .with_note("car takes a pair")
+        .with_help("check for the empty list first");
+    // outside the source map only the position is known
+    assert_eq!(diagnostic.render(&sources, Colour::Plain), concat!(
+        "error: wrong type of argument 1: expected pair\n",
+        " --> 1:1\n",
+        "  = note: car takes a pair\n",
+        "  = help: check for the empty list first\n",
+    ));
+
+    // runtime errors underline the whole call
+    let main = sources.add("main.scm", "(define v (vector 1 2))\n(display (vector-ref v 5))\n");
+    let text = sources.get(main).unwrap().text.clone();
+    let error = Processor::from(Lexer::with_options(text.chars(), LexerOptions{file: main, ..LexerOptions::default()}))
+        .map(|Located{data, span}| interpreter.eval(&data.unwrap().with_span(span)))
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(Diagnostic::from(&error).render(&sources, Colour::Plain), concat!(
+        "error: index 5 out of range\n",
+        " --> main.scm:2:10\n",
+        "  |\n",
+        "2 | (display (vector-ref v 5))\n",
+        "  |          ^^^^^^^^^^^^^^^^ in this call\n",
+    ));
+
+    let span = Span{
+        start: super::Location{file, row: 2, col: 2, offset: 23},
+        end: super::Location{file, row: 2, col: 3, offset: 24},
+    };
+    let diagnostic = Diagnostic::new("odd", span)
+        .with_label("this")
+        .with_secondary(Span{start: super::Location{file, ..Default::default()}, end: span.start}, "in here");
+    assert_eq!(diagnostic.render(&sources, Colour::Plain), concat!(
+        "error: odd\n",
+        " --> lib/list.scm:3:3\n",
+        "  |\n",
+        "3 | \t\t2)\n",
+        "  | \t\t^ this\n",
+        "  |\n",
+        "1 | (define xs\n",
+        "  | ---------- in here\n",
+    ));
+    assert_eq!(
+        Diagnostic::new("odd", span).render(&sources, Colour::Ansi).lines().next(),
+        Some("\x1b[1;31merror\x1b[0m\x1b[1m: odd\x1b[0m"),
+    );
+}
@@ -1,11 +1,151 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
+use super::{Value, Arity, IntoNative, RuntimeError, library};
 
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+// A lexical environment: a chain of frames ending in the global (top-level)
+// frame. Cloning an `Environment` yields another handle to the same frames.
+#[derive(Clone, Default)]
 pub struct Environment {
+    frame: Rc<Frame>,
+}
 
+#[derive(Default)]
+struct Frame {
+    bindings: RefCell<HashMap<String, Value>>,
+    parent: Option<Rc<Frame>>,
 }
 
 impl Environment {
+    // Creates an empty global environment.
     pub fn new() -> Self {
-        Self{}
+        Self::default()
     }
-}
\ No newline at end of file
+
+    // Creates a global environment holding the standard procedures.
+    pub fn standard() -> Self {
+        let environment = Self::new();
+        library::install(&environment);
+        environment
+    }
+
+    // Creates a new frame whose parent is `self`.
+    pub fn extend(&self) -> Self {
+        Self {
+            frame: Rc::new(Frame {
+                bindings: RefCell::new(HashMap::new()),
+                parent: Some(self.frame.clone()),
+            }),
+        }
+    }
+
+    // The outermost frame of the chain.
+    pub fn global(&self) -> Self {
+        let mut frame = &self.frame;
+        while let Some(parent) = &frame.parent {
+            frame = parent;
+        }
+        Self { frame: frame.clone() }
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.frame.parent.is_none()
+    }
+
+    // Binds `name` in the innermost frame, shadowing any outer binding and
+    // replacing an existing binding in the same frame.
+    pub fn define(&self, name: &str, value: Value) {
+        self.frame.bindings.borrow_mut().insert(name.to_string(), value);
+    }
+
+    // Binds `name` to a procedure implemented by `function`. The interpreter
+    // checks the argument count against `arity` before the call, so
+    // `function` may index the required arguments directly; errors it returns
+    // are reported at the location of the call.
+    pub fn define_native<F>(&self, name: &str, arity: Arity, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value> + 'static,
+    {
+        self.define(name, Value::native(name, arity, function));
+    }
+
+    // Binds `name` to a procedure that converts its arguments to the
+    // parameter types of `function` and its result back to a value.
+    // A failed conversion reports the argument position and expected type.
+    pub fn define_function<F, P>(&self, name: &str, function: F)
+    where
+        F: IntoNative<P> + 'static,
+    {
+        let arity = function.arity();
+        self.define_native(name, arity, move |arguments| function.call(arguments));
+    }
+
+    pub fn lookup(&self, name: &str) -> Result<Value> {
+        match self.find(name) {
+            Some(frame) => Ok(frame.bindings.borrow()[name].clone()),
+            None => error!(RuntimeError::UnboundVariable(name.to_string())),
+        }
+    }
+
+    pub fn is_bound(&self, name: &str) -> bool {
+        self.find(name).is_some()
+    }
+
+    // Updates the nearest binding of `name`; `set!` never creates bindings.
+    pub fn set(&self, name: &str, value: Value) -> Result<()> {
+        match self.find(name) {
+            Some(frame) => {
+                frame.bindings.borrow_mut().insert(name.to_string(), value);
+                Ok(())
+            },
+            None => error!(RuntimeError::UndefinedAssignment(name.to_string())),
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<&Frame> {
+        let mut frame = self.frame.as_ref();
+        loop {
+            if frame.bindings.borrow().contains_key(name) {
+                return Some(frame);
+            }
+            frame = frame.parent.as_deref()?;
+        }
+    }
+}
+
+#[test]
+fn shadowing() -> Result<()> {
+    let global = Environment::new();
+    global.define("x", Value::Boolean(true));
+    global.define("y", Value::Boolean(true));
+
+    let local = global.extend();
+    local.define("x", Value::Boolean(false));
+    assert_eq!(local.lookup("x")?, Value::Boolean(false));
+    assert_eq!(local.lookup("y")?, Value::Boolean(true));
+    assert_eq!(global.lookup("x")?, Value::Boolean(true));
+
+    assert!(global.is_global());
+    assert!(!local.is_global());
+    assert!(local.global().lookup("x")? == Value::Boolean(true));
+    Ok(())
+}
+
+#[test]
+fn mutation() -> Result<()> {
+    let global = Environment::new();
+    global.define("x", Value::Null);
+    let local = global.extend().extend();
+
+    local.set("x", Value::Boolean(false))?;
+    assert_eq!(global.lookup("x")?, Value::Boolean(false));
+    assert!(!local.extend().is_bound("y"));
+
+    assert_eq!(local.lookup("y"), Err(RuntimeError::UnboundVariable("y".to_string())));
+    assert_eq!(local.set("y", Value::Null), Err(RuntimeError::UndefinedAssignment("y".to_string())));
+    assert!(!global.is_bound("y"));
+    Ok(())
+}
@@ -1,12 +1,434 @@
+use std::rc::Rc;
 
+use super::{Datum, DatumPair, Located, Location, ToLocated, RuntimeError, Value, Arity};
+use super::value::Labels;
+
+type Result<T> = std::result::Result<T, Located<RuntimeError>>;
+
+pub type Node = Rc<Located<Expression>>;
+pub type Body = Rc<[Node]>;
+
+// Core forms the interpreter executes. Derived forms (let, let*, letrec,
+// when, unless, ...) are rewritten into these during analysis.
 #[derive(Debug)]
 pub enum Expression {
-    Nil,
-    Boolean(bool),
-    Int(i64),
-    Float(f64),
-    Str(String),
-    Cons(Box<Expression>, Box<Expression>),
-    Symbol(String),
-    NoMatch,
-}
\ No newline at end of file
+    Constant(Value),
+    Variable(String),
+    Define(String, Node),
+    Set(String, Node),
+    If(Node, Node, Option<Node>),
+    Lambda(Rc<Lambda>),
+    Begin(Body),
+    Call(Node, Body),
+    And(Body),
+    Or(Body),
+    Cond(Rc<[CondClause]>),
+    Case(Node, Rc<[CaseClause]>, Option<ClauseBody>),
+    // the condition variable, the clauses and the body
+    Guard(String, Rc<[CondClause]>, Body),
+}
+
+impl ToLocated for Expression {}
+
+#[derive(Debug)]
+pub struct Lambda {
+    pub name: Option<String>,
+    pub parameters: Vec<String>,
+    pub rest: Option<String>,
+    pub body: Body,
+}
+
+impl Lambda {
+    pub fn arity(&self) -> Arity {
+        match self.rest {
+            None => Arity::Exactly(self.parameters.len()),
+            Some(_) => Arity::AtLeast(self.parameters.len()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CondClause {
+    pub test: Node,
+    pub body: ClauseBody,
+}
+
+#[derive(Debug)]
+pub struct CaseClause {
+    pub data: Vec<Value>,
+    pub body: ClauseBody,
+}
+
+#[derive(Debug)]
+pub enum ClauseBody {
+    // an empty sequence yields the value of the clause test
+    Sequence(Body),
+    // `=> receiver`
+    Receiver(Node),
+}
+
+impl Expression {
+    pub fn analyze(datum: &Located<Datum>) -> Result<Node> {
+        analyze(datum, &mut Labels::default())
+    }
+}
+
+// `labels` holds the objects named so far in the quoted data of the top-level
+// datum, which all share the scope of its datum labels.
+fn analyze(datum: &Located<Datum>, labels: &mut Labels) -> Result<Node> {
+    let location = datum.location();
+    let expression = match &datum.data {
+        Datum::Symbol(name) => Expression::Variable(name.clone()),
+        Datum::Pair(pair) => match pair.as_ref() {
+            DatumPair::Empty => return located_error!(RuntimeError::BadSyntax("()".to_string()), location),
+            DatumPair::Some(_, _) => analyze_compound(datum, labels)?,
+        },
+        // labels only make sense within quoted data
+        Datum::Labelled(_, datum) => return analyze(datum, labels),
+        Datum::Reference(n) => return located_error!(RuntimeError::BadSyntax(format!("#{}#", n)), location),
+        _ => Expression::Constant(constant(datum, labels)?),
+    };
+    Ok(Rc::new(expression.with_span(datum.span)))
+}
+
+fn constant(datum: &Located<Datum>, labels: &mut Labels) -> Result<Value> {
+    labels.value(datum).map_err(|Located{data: e, span}| RuntimeError::ProcessorError(e).with_span(span))
+}
+
+fn node(expression: Expression, location: Location) -> Node {
+    Rc::new(expression.with_location(location))
+}
+
+fn analyze_compound(datum: &Located<Datum>, labels: &mut Labels) -> Result<Expression> {
+    let location = datum.location();
+    let elements = match proper_list(datum) {
+        Some(elements) => elements,
+        None => return located_error!(RuntimeError::BadSyntax("application".to_string()), location),
+    };
+    let keyword = match &elements[0].data {
+        Datum::Symbol(symbol) => symbol.as_str(),
+        _ => "",
+    };
+    let operands = &elements[1..];
+    let bad_syntax = || located_error!(RuntimeError::BadSyntax(keyword.to_string()), location);
+
+    Ok(match (keyword, operands) {
+        ("quote", [datum]) => Expression::Constant(constant(datum, labels)?),
+        ("quote", _) => return bad_syntax(),
+
+        ("if", [test, consequent]) => Expression::If(
+            analyze(test, labels)?,
+            analyze(consequent, labels)?,
+            None,
+        ),
+        ("if", [test, consequent, alternative]) => Expression::If(
+            analyze(test, labels)?,
+            analyze(consequent, labels)?,
+            Some(analyze(alternative, labels)?),
+        ),
+        ("if", _) => return bad_syntax(),
+
+        ("define", [target, rest @ ..]) => match (&target.data, rest) {
+            (Datum::Symbol(name), [value]) => Expression::Define(name.clone(), analyze(value, labels)?),
+            (Datum::Pair(_), body) if !body.is_empty() => {
+                let (name, formals) = match target.data.clone() {
+                    Datum::Pair(pair) => match *pair {
+                        DatumPair::Some(Located{data: Datum::Symbol(name), ..}, formals) => (name, formals),
+                        _ => return bad_syntax(),
+                    },
+                    _ => unreachable!(),
+                };
+                let lambda = analyze_lambda(Some(name.clone()), &formals, body, keyword, location, labels)?;
+                Expression::Define(name, node(Expression::Lambda(Rc::new(lambda)), location))
+            },
+            _ => return bad_syntax(),
+        },
+        ("define", _) => return bad_syntax(),
+
+        ("set!", [target, value]) => match &target.data {
+            Datum::Symbol(name) => Expression::Set(name.clone(), analyze(value, labels)?),
+            _ => return bad_syntax(),
+        },
+        ("set!", _) => return bad_syntax(),
+
+        ("lambda", [formals, body @ ..]) if !body.is_empty() =>
+            Expression::Lambda(Rc::new(analyze_lambda(None, formals, body, keyword, location, labels)?)),
+        ("lambda", _) => return bad_syntax(),
+
+        ("begin", forms) => Expression::Begin(analyze_sequence(forms, labels)?),
+
+        ("let", [Located{data: Datum::Symbol(name), ..}, bindings, body @ ..]) if !body.is_empty() => {
+            // (let name ((v e) ...) body) => ((letrec ((name (lambda (v ...) body))) name) e ...)
+            let (parameters, inits) = analyze_bindings(bindings, keyword, labels)?;
+            let lambda = Lambda {
+                name: Some(name.clone()),
+                parameters,
+                rest: None,
+                body: analyze_body(body, keyword, location, labels)?,
+            };
+            let define = node(Expression::Define(
+                name.clone(),
+                node(Expression::Lambda(Rc::new(lambda)), location),
+            ), location);
+            let scope = Lambda {
+                name: None,
+                parameters: vec![],
+                rest: None,
+                body: Rc::new([define, node(Expression::Variable(name.clone()), location)]),
+            };
+            let procedure = node(Expression::Call(
+                node(Expression::Lambda(Rc::new(scope)), location),
+                Rc::new([]),
+            ), location);
+            Expression::Call(procedure, inits.into())
+        },
+        ("let", [bindings, body @ ..]) if !body.is_empty() => {
+            let (parameters, inits) = analyze_bindings(bindings, keyword, labels)?;
+            let lambda = Lambda {
+                name: None,
+                parameters,
+                rest: None,
+                body: analyze_body(body, keyword, location, labels)?,
+            };
+            Expression::Call(node(Expression::Lambda(Rc::new(lambda)), location), inits.into())
+        },
+        ("let", _) => return bad_syntax(),
+
+        ("let*", [bindings, body @ ..]) if !body.is_empty() => {
+            let (parameters, inits) = analyze_bindings(bindings, keyword, labels)?;
+            let mut body = analyze_body(body, keyword, location, labels)?;
+            // nest one single-binding let per binding, innermost first
+            let mut expression = None;
+            for (parameter, init) in parameters.into_iter().zip(inits).rev() {
+                if let Some(inner) = expression.take() {
+                    body = Rc::new([node(inner, location)]);
+                }
+                let lambda = Lambda {
+                    name: None,
+                    parameters: vec![parameter],
+                    rest: None,
+                    body: body.clone(),
+                };
+                expression = Some(Expression::Call(
+                    node(Expression::Lambda(Rc::new(lambda)), location),
+                    Rc::new([init]),
+                ));
+            }
+            expression.unwrap_or_else(|| Expression::Call(
+                node(Expression::Lambda(Rc::new(Lambda{name: None, parameters: vec![], rest: None, body})), location),
+                Rc::new([]),
+            ))
+        },
+        ("let*", _) => return bad_syntax(),
+
+        ("letrec", [bindings, body @ ..]) |
+        ("letrec*", [bindings, body @ ..]) if !body.is_empty() => {
+            // bindings become internal definitions of a fresh scope
+            let (names, inits) = analyze_bindings(bindings, keyword, labels)?;
+            let mut forms = names.into_iter().zip(inits)
+                .map(|(name, init)| node(Expression::Define(name, init), location))
+                .collect::<Vec<_>>();
+            forms.extend(analyze_body(body, keyword, location, labels)?.iter().cloned());
+            let lambda = Lambda {
+                name: None,
+                parameters: vec![],
+                rest: None,
+                body: forms.into(),
+            };
+            Expression::Call(node(Expression::Lambda(Rc::new(lambda)), location), Rc::new([]))
+        },
+        ("letrec", _) | ("letrec*", _) => return bad_syntax(),
+
+        ("cond", clauses) if !clauses.is_empty() => Expression::Cond(analyze_cond_clauses(clauses, keyword, location, labels)?),
+        ("cond", _) => return bad_syntax(),
+
+        ("case", [key, clauses @ ..]) if !clauses.is_empty() => {
+            let mut analyzed = vec![];
+            let mut otherwise = None;
+            for (i, clause) in clauses.iter().enumerate() {
+                let parts = match proper_list(clause) {
+                    Some(parts) if parts.len() >= 2 => parts,
+                    _ => return bad_syntax(),
+                };
+                let body = match analyze_clause_body(&parts[1..], labels) {
+                    Some(body) => body?,
+                    None => return bad_syntax(),
+                };
+                match &parts[0].data {
+                    Datum::Symbol(s) if s == "else" && i + 1 == clauses.len() => otherwise = Some(body),
+                    _ => match proper_list(parts[0]) {
+                        Some(data) => analyzed.push(CaseClause{
+                            data: data.iter().map(|d| constant(d, labels)).collect::<Result<_>>()?,
+                            body,
+                        }),
+                        None => return bad_syntax(),
+                    },
+                }
+            }
+            Expression::Case(analyze(key, labels)?, analyzed.into(), otherwise)
+        },
+        ("case", _) => return bad_syntax(),
+
+        ("and", forms) => Expression::And(analyze_sequence(forms, labels)?),
+        ("or", forms) => Expression::Or(analyze_sequence(forms, labels)?),
+
+        ("when", [test, body @ ..]) if !body.is_empty() => Expression::If(
+            analyze(test, labels)?,
+            node(Expression::Begin(analyze_sequence(body, labels)?), location),
+            None,
+        ),
+        ("unless", [test, body @ ..]) if !body.is_empty() => Expression::If(
+            analyze(test, labels)?,
+            node(Expression::Constant(Value::Unspecified), location),
+            Some(node(Expression::Begin(analyze_sequence(body, labels)?), location)),
+        ),
+        ("when", _) | ("unless", _) => return bad_syntax(),
+
+        ("guard", [specification, body @ ..]) if !body.is_empty() => match proper_list(specification).as_deref() {
+            Some([Located{data: Datum::Symbol(variable), ..}, clauses @ ..]) if !clauses.is_empty() => Expression::Guard(
+                variable.clone(),
+                analyze_cond_clauses(clauses, keyword, location, labels)?,
+                analyze_sequence(body, labels)?,
+            ),
+            _ => return bad_syntax(),
+        },
+        ("guard", _) => return bad_syntax(),
+
+        _ => Expression::Call(analyze(elements[0], labels)?, analyze_sequence(operands, labels)?),
+    })
+}
+
+fn analyze_sequence(forms: &[&Located<Datum>], labels: &mut Labels) -> Result<Body> {
+    forms.iter().map(|form| analyze(form, labels)).collect()
+}
+
+fn analyze_body(forms: &[&Located<Datum>], keyword: &str, location: Location, labels: &mut Labels) -> Result<Body> {
+    if forms.is_empty() {
+        return located_error!(RuntimeError::BadSyntax(keyword.to_string()), location);
+    }
+    analyze_sequence(forms, labels)
+}
+
+// Clauses of `cond` and `guard`; an `else` clause gets a true test.
+fn analyze_cond_clauses(
+    clauses: &[&Located<Datum>],
+    keyword: &str,
+    location: Location,
+    labels: &mut Labels,
+) -> Result<Rc<[CondClause]>> {
+    let bad_syntax = || located_error!(RuntimeError::BadSyntax(keyword.to_string()), location);
+    let mut analyzed = vec![];
+    for (i, clause) in clauses.iter().enumerate() {
+        let parts = match proper_list(clause) {
+            Some(parts) if !parts.is_empty() => parts,
+            _ => return bad_syntax(),
+        };
+        let test = match &parts[0].data {
+            Datum::Symbol(s) if s == "else" => {
+                if i + 1 != clauses.len() || parts.len() == 1 {
+                    return bad_syntax();
+                }
+                node(Expression::Constant(Value::Boolean(true)), parts[0].location())
+            },
+            _ => analyze(parts[0], labels)?,
+        };
+        let body = match analyze_clause_body(&parts[1..], labels) {
+            Some(body) => body?,
+            None => return bad_syntax(),
+        };
+        analyzed.push(CondClause{test, body});
+    }
+    Ok(analyzed.into())
+}
+
+// Returns None when the clause body is malformed, e.g. `(test =>)`.
+fn analyze_clause_body(forms: &[&Located<Datum>], labels: &mut Labels) -> Option<Result<ClauseBody>> {
+    match forms {
+        [Located{data: Datum::Symbol(arrow), ..}, receiver] if arrow == "=>" =>
+            Some(analyze(receiver, labels).map(ClauseBody::Receiver)),
+        [Located{data: Datum::Symbol(arrow), ..}, ..] if arrow == "=>" => None,
+        forms => Some(analyze_sequence(forms, labels).map(ClauseBody::Sequence)),
+    }
+}
+
+fn analyze_lambda(
+    name: Option<String>,
+    formals: &Located<Datum>,
+    body: &[&Located<Datum>],
+    keyword: &str,
+    location: Location,
+    labels: &mut Labels,
+) -> Result<Lambda> {
+    let bad_syntax = || located_error!(RuntimeError::BadSyntax(keyword.to_string()), location);
+
+    let (elements, tail) = list_parts(formals);
+    let mut parameters: Vec<String> = vec![];
+    for element in elements {
+        match &element.data {
+            Datum::Symbol(s) if !parameters.contains(s) => parameters.push(s.clone()),
+            _ => return bad_syntax(),
+        }
+    }
+    let rest = match &tail.data {
+        Datum::Symbol(s) if !parameters.contains(s) => Some(s.clone()),
+        Datum::Pair(_) => None,
+        _ => return bad_syntax(),
+    };
+
+    Ok(Lambda {
+        name,
+        parameters,
+        rest,
+        body: analyze_body(body, keyword, location, labels)?,
+    })
+}
+
+fn analyze_bindings(bindings: &Located<Datum>, keyword: &str, labels: &mut Labels) -> Result<(Vec<String>, Vec<Node>)> {
+    let bad_syntax = || located_error!(RuntimeError::BadSyntax(keyword.to_string()), bindings.location());
+
+    let bindings = match proper_list(bindings) {
+        Some(bindings) => bindings,
+        None => return bad_syntax(),
+    };
+    let mut names = vec![];
+    let mut inits = vec![];
+    for binding in bindings {
+        match proper_list(binding).as_deref() {
+            // 4.2.2 only the variables of `let*` may repeat, as of `lambda`
+            // parameters those of the other forms may not
+            Some([Located{data: Datum::Symbol(name), ..}, _]) if keyword != "let*" && names.contains(name) => {
+                return bad_syntax();
+            },
+            Some([Located{data: Datum::Symbol(name), ..}, init]) => {
+                names.push(name.clone());
+                inits.push(analyze(init, labels)?);
+            },
+            _ => return bad_syntax(),
+        }
+    }
+    Ok((names, inits))
+}
+
+// Splits a list datum into its elements and its final cdr, which is `()` for
+// a proper list.
+fn list_parts(datum: &Located<Datum>) -> (Vec<&Located<Datum>>, &Located<Datum>) {
+    let mut elements = vec![];
+    let mut current = datum;
+    while let Datum::Pair(pair) = &current.data {
+        match pair.as_ref() {
+            DatumPair::Some(car, cdr) => {
+                elements.push(car);
+                current = cdr;
+            },
+            DatumPair::Empty => break,
+        }
+    }
+    (elements, current)
+}
+
+fn proper_list(datum: &Located<Datum>) -> Option<Vec<&Located<Datum>>> {
+    match list_parts(datum) {
+        (elements, Located{data: Datum::Pair(_), ..}) => Some(elements),
+        _ => None,
+    }
+}
@@ -1,17 +1,1421 @@
-use super::{Expression, Environment};
+use std::rc::Rc;
+use std::path::Path;
 
+use super::{Environment, Expression, Value, Procedure, Control, Arity, IntoNative, Closure, ErrorObject, ErrorKind, Datum, Located, Span, ToLocated, RuntimeError, Lexer, LexerOptions, Processor, SourceMap, FileId, LoadError};
+use super::expression::{Node, Body, CondClause, ClauseBody};
+#[cfg(test)]
+use super::Location;
+
+type Result<T> = std::result::Result<T, Located<RuntimeError>>;
+
+// Number of pending frames allowed before evaluation fails with
+// `RecursionLimitExceeded`.
+const DEFAULT_STACK_LIMIT: usize = 1_000_000;
+
+// Evaluation is a loop over an explicit continuation stack instead of Rust
+// recursion, so deep Scheme recursion only grows `stack` and calls in tail
+// position push no frame at all.
 pub struct Interpreter {
     environment: Environment,
+    stack: Vec<Frame>,
+    winders: Winders,
+    handlers: Handlers,
+    stack_limit: usize,
+    // the files read by `load`, which their locations refer to
+    sources: SourceMap,
+}
+
+// A captured continuation: the frames to return through, the dynamic-wind
+// extents and the exception handlers it runs with. Invoking it may happen any
+// number of times, so the frames are copied back rather than moved.
+pub struct Continuation {
+    stack: Vec<Frame>,
+    winders: Winders,
+    handlers: Handlers,
+}
+
+// The `before`/`after` thunks of the active `dynamic-wind` calls, innermost
+// first. Continuations share the list.
+type Winders = Option<Rc<Wind>>;
+
+struct Wind {
+    before: Value,
+    after: Value,
+    outer: Winders,
+    depth: usize,
+}
+
+// The installed exception handlers, innermost first.
+type Handlers = Option<Rc<Handler>>;
+
+struct Handler {
+    kind: HandlerKind,
+    outer: Handlers,
+}
+
+enum HandlerKind {
+    // installed by `with-exception-handler`
+    Procedure(Value),
+    // a `guard` expression with its environment, and the stack height and
+    // extents at its entry, which are restored before its clauses run
+    Guard(Node, Environment, usize, Winders),
+}
+
+fn depth(winders: &Winders) -> usize {
+    winders.as_ref().map_or(0, |wind| wind.depth)
+}
+
+// What remains to be done once the value of a subexpression is known.
+#[derive(Clone)]
+enum Frame {
+    // evaluate the body from the given index on
+    Sequence(Body, usize, Environment),
+    // pick a branch of an `if`
+    If(Node, Option<Node>, Environment),
+    Define(String, Environment),
+    Set(String, Environment, Span),
+    // the call node and the operator and operands evaluated so far
+    Call(Node, Vec<Value>, Environment),
+    // the remaining tests from the given index on
+    And(Body, usize, Environment),
+    Or(Body, usize, Environment),
+    // the index of the clause whose test is being evaluated
+    Cond(Rc<[CondClause]>, usize, Environment, Span),
+    // the `case` node whose key is being evaluated
+    Case(Node, Environment),
+    // apply the receiver of a `=>` clause to the test value
+    Receiver(Value, Span),
+    // apply the consumer of `call-with-values` to the produced values
+    Consumer(Value, Span),
+    // `dynamic-wind`: the `before` thunk returned; enter the extent and call
+    // the thunk
+    WindBefore(Value, Value, Value, Span),
+    // `dynamic-wind`: the thunk returned; leave the extent and call `after`
+    WindAfter(Span),
+    // return this value, ignoring the one received
+    Restore(Value),
+    // call the remaining wind thunks, last first, each in its own extent,
+    // then continue with the continuation
+    Rewind(Vec<(Value, Winders)>, Rc<Continuation>, Value, Span),
+    // reinstate these handlers when leaving their extent
+    Handlers(Handlers),
+    // a handler returned from a non-continuable `raise` of the value
+    Raised(Value, Span),
+    // the `guard` node receiving the condition, and the continuation of
+    // the `raise` to re-raise from when no clause applies
+    Guard(Node, Environment, Rc<Continuation>),
+    // the index of the `guard` clause whose test is being evaluated
+    GuardClause(Node, usize, Environment, Rc<Continuation>),
+    // re-raise the condition with `raise-continuable`
+    Reraise(Value, Span),
+}
+
+enum State {
+    Eval(Node, Environment),
+    Return(Value),
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        Self::with_environment(Environment::standard())
+    }
+
+    // Runs top-level forms in `environment`, which the host may have
+    // pre-populated with its own definitions.
+    pub fn with_environment(environment: Environment) -> Self {
         Self {
-            environment: Environment::new(),
+            environment,
+            stack: vec![],
+            winders: None,
+            handlers: None,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            sources: SourceMap::new(),
+        }
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    // Defines a native procedure in the top-level environment; see
+    // `Environment::define_native`.
+    pub fn define_native<F>(&self, name: &str, arity: Arity, function: F)
+    where
+        F: Fn(&[Value]) -> std::result::Result<Value, RuntimeError> + 'static,
+    {
+        self.environment.define_native(name, arity, function);
+    }
+
+    // Defines a native procedure with typed parameters in the top-level
+    // environment; see `Environment::define_function`.
+    pub fn define_function<F, P>(&self, name: &str, function: F)
+    where
+        F: IntoNative<P> + 'static,
+    {
+        self.environment.define_function(name, function);
+    }
+
+    // Bounds the depth of non-tail recursion, and so the memory it may use.
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.stack_limit = limit;
+    }
+
+    pub fn eval(&mut self, datum: &Located<Datum>) -> Result<Value> {
+        let expression = Expression::analyze(datum)?;
+        let environment = self.environment.clone();
+        let result = self.run(expression, environment);
+        self.stack.clear();
+        self.winders = None;
+        self.handlers = None;
+        result
+    }
+
+    // Reads and evaluates every datum in `text`, returning the last value.
+    pub fn eval_str(&mut self, text: &str) -> Result<Value> {
+        self.eval_text(text, FileId::default())
+    }
+
+    // Like `eval_str` on the contents of the file at `path`, which is added
+    // to `sources` so that locations in it can be resolved.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> std::result::Result<Value, LoadError> {
+        let path = path.as_ref();
+        let file = self.sources.load(path)
+            .map_err(|e| LoadError::Io(path.to_string_lossy().into_owned(), e))?;
+        let text = self.sources.get(file).unwrap().text.clone();
+        Ok(self.eval_text(&text, file)?)
+    }
+
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    fn eval_text(&mut self, text: &str, file: FileId) -> Result<Value> {
+        let options = LexerOptions{file, ..LexerOptions::default()};
+        let mut result = Value::Unspecified;
+        for Located{data, span} in Processor::from(Lexer::with_options(text.chars(), options)) {
+            let datum = data.map_err(|e| RuntimeError::ProcessorError(e).with_span(span))?;
+            result = self.eval(&datum.with_span(span))?;
+        }
+        Ok(result)
+    }
+
+    fn run(&mut self, node: Node, environment: Environment) -> Result<Value> {
+        let mut state = State::Eval(node, environment);
+        loop {
+            let next = match state {
+                State::Eval(node, environment) => self.execute(node, environment),
+                State::Return(value) => match self.stack.pop() {
+                    Some(frame) => self.resume(frame, value),
+                    None => return Ok(value),
+                },
+            };
+            state = next.or_else(|error| self.signal(error))?;
+        }
+    }
+
+    fn push(&mut self, frame: Frame, span: Span) -> Result<()> {
+        if self.stack.len() >= self.stack_limit {
+            return Err(RuntimeError::RecursionLimitExceeded.with_span(span));
+        }
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    // Starts evaluating `node`: either produces its value directly or pushes
+    // the frame waiting for its first subexpression.
+    fn execute(&mut self, node: Node, environment: Environment) -> Result<State> {
+        let span = node.span;
+        Ok(match &node.data {
+            Expression::Constant(value) => State::Return(value.clone()),
+            Expression::Variable(name) => State::Return(environment.lookup(name).map_err(|e| e.with_span(span))?),
+            Expression::Define(name, value) => {
+                self.push(Frame::Define(name.clone(), environment.clone()), span)?;
+                State::Eval(value.clone(), environment)
+            },
+            Expression::Set(name, value) => {
+                self.push(Frame::Set(name.clone(), environment.clone(), span), span)?;
+                State::Eval(value.clone(), environment)
+            },
+            Expression::If(test, consequent, alternative) => {
+                self.push(Frame::If(consequent.clone(), alternative.clone(), environment.clone()), span)?;
+                State::Eval(test.clone(), environment)
+            },
+            Expression::Lambda(lambda) => State::Return(Value::Procedure(Procedure::Closure(Rc::new(Closure {
+                lambda: lambda.clone(),
+                environment,
+            })))),
+            Expression::Begin(body) => self.execute_sequence(body.clone(), 0, environment, span)?,
+            Expression::Call(operator, _) => {
+                let operator = operator.clone();
+                self.push(Frame::Call(node, vec![], environment.clone()), span)?;
+                State::Eval(operator, environment)
+            },
+            Expression::And(tests) if tests.is_empty() => State::Return(Value::Boolean(true)),
+            Expression::And(tests) => self.execute_and(tests.clone(), 0, environment, span)?,
+            Expression::Or(tests) if tests.is_empty() => State::Return(Value::Boolean(false)),
+            Expression::Or(tests) => self.execute_or(tests.clone(), 0, environment, span)?,
+            Expression::Cond(clauses) if clauses.is_empty() => State::Return(Value::Unspecified),
+            Expression::Cond(clauses) => {
+                let test = clauses[0].test.clone();
+                self.push(Frame::Cond(clauses.clone(), 0, environment.clone(), span), span)?;
+                State::Eval(test, environment)
+            },
+            Expression::Case(key, _, _) => {
+                let key = key.clone();
+                self.push(Frame::Case(node, environment.clone()), span)?;
+                State::Eval(key, environment)
+            },
+            Expression::Guard(_, _, body) => {
+                let body = body.clone();
+                let height = self.stack.len();
+                self.push(Frame::Handlers(self.handlers.clone()), span)?;
+                self.handlers = Some(Rc::new(Handler {
+                    kind: HandlerKind::Guard(node, environment.clone(), height, self.winders.clone()),
+                    outer: self.handlers.take(),
+                }));
+                self.execute_sequence(body, 0, environment, span)?
+            },
+        })
+    }
+
+    // Continues the computation waiting in `frame` with `value`.
+    fn resume(&mut self, frame: Frame, value: Value) -> Result<State> {
+        Ok(match frame {
+            Frame::Sequence(body, next, environment) => {
+                let span = body[next].span;
+                self.execute_sequence(body, next, environment, span)?
+            },
+            Frame::If(consequent, alternative, environment) => match (value.is_true(), alternative) {
+                (true, _) => State::Eval(consequent, environment),
+                (false, Some(alternative)) => State::Eval(alternative, environment),
+                (false, None) => State::Return(Value::Unspecified),
+            },
+            Frame::Define(name, environment) => {
+                environment.define(&name, value);
+                State::Return(Value::Unspecified)
+            },
+            Frame::Set(name, environment, span) => {
+                environment.set(&name, value).map_err(|e| e.with_span(span))?;
+                State::Return(Value::Unspecified)
+            },
+            Frame::Call(node, mut values, environment) => {
+                let operands = match &node.data {
+                    Expression::Call(_, operands) => operands,
+                    _ => unreachable!(),
+                };
+                values.push(value);
+                match operands.get(values.len() - 1) {
+                    Some(operand) => {
+                        let operand = operand.clone();
+                        let span = node.span;
+                        self.push(Frame::Call(node, values, environment.clone()), span)?;
+                        State::Eval(operand, environment)
+                    },
+                    None => {
+                        let procedure = values.remove(0);
+                        self.apply(procedure, values, node.span)?
+                    },
+                }
+            },
+            Frame::And(tests, next, environment) => match value.is_true() {
+                true => {
+                    let span = tests[next].span;
+                    self.execute_and(tests, next, environment, span)?
+                },
+                false => State::Return(value),
+            },
+            Frame::Or(tests, next, environment) => match value.is_true() {
+                true => State::Return(value),
+                false => {
+                    let span = tests[next].span;
+                    self.execute_or(tests, next, environment, span)?
+                },
+            },
+            Frame::Cond(clauses, i, environment, span) => {
+                if value.is_true() {
+                    self.execute_clause(&clauses[i].body, value, environment, span)?
+                } else if i + 1 < clauses.len() {
+                    let test = clauses[i + 1].test.clone();
+                    self.push(Frame::Cond(clauses, i + 1, environment.clone(), span), span)?;
+                    State::Eval(test, environment)
+                } else {
+                    State::Return(Value::Unspecified)
+                }
+            },
+            Frame::Case(node, environment) => {
+                let (clauses, otherwise) = match &node.data {
+                    Expression::Case(_, clauses, otherwise) => (clauses, otherwise),
+                    _ => unreachable!(),
+                };
+                match clauses.iter().find(|clause| clause.data.iter().any(|datum| datum.eqv(&value))) {
+                    Some(clause) => self.execute_clause(&clause.body, value, environment, node.span)?,
+                    None => match otherwise {
+                        Some(body) => self.execute_clause(body, value, environment, node.span)?,
+                        None => State::Return(Value::Unspecified),
+                    },
+                }
+            },
+            Frame::Receiver(test, span) => self.apply(value, vec![test], span)?,
+            Frame::Consumer(consumer, span) => {
+                let arguments = match value {
+                    Value::Values(values) => values.to_vec(),
+                    value => vec![value],
+                };
+                self.apply(consumer, arguments, span)?
+            },
+            Frame::WindBefore(before, thunk, after, span) => {
+                self.winders = Some(Rc::new(Wind {
+                    before,
+                    after,
+                    depth: depth(&self.winders) + 1,
+                    outer: self.winders.take(),
+                }));
+                self.push(Frame::WindAfter(span), span)?;
+                self.apply(thunk, vec![], span)?
+            },
+            Frame::WindAfter(span) => {
+                let wind = self.winders.take().expect("dynamic-wind extent");
+                self.winders = wind.outer.clone();
+                self.push(Frame::Restore(value), span)?;
+                self.apply(wind.after.clone(), vec![], span)?
+            },
+            Frame::Restore(value) => State::Return(value),
+            Frame::Rewind(steps, continuation, value, span) => self.rewind(steps, continuation, value, span)?,
+            Frame::Handlers(handlers) => {
+                self.handlers = handlers;
+                State::Return(value)
+            },
+            Frame::Raised(condition, span) => return Err(RuntimeError::HandlerReturned(condition).with_span(span)),
+            Frame::Guard(node, environment, reraise) => {
+                let environment = environment.extend();
+                if let Expression::Guard(variable, _, _) = &node.data {
+                    environment.define(variable, value);
+                }
+                self.execute_guard_clause(node, 0, environment, reraise)?
+            },
+            Frame::GuardClause(node, i, environment, reraise) => {
+                let clauses = match &node.data {
+                    Expression::Guard(_, clauses, _) => clauses.clone(),
+                    _ => unreachable!(),
+                };
+                if value.is_true() {
+                    self.execute_clause(&clauses[i].body, value, environment, node.span)?
+                } else {
+                    self.execute_guard_clause(node, i + 1, environment, reraise)?
+                }
+            },
+            Frame::Reraise(condition, span) => self.raise(condition, true, span)?,
+        })
+    }
+
+    // Evaluates `body` from `next` on; the last expression is in tail
+    // position and evaluated without a frame.
+    fn execute_sequence(&mut self, body: Body, next: usize, environment: Environment, span: Span) -> Result<State> {
+        Ok(match body.len() - next {
+            0 => State::Return(Value::Unspecified),
+            1 => State::Eval(body[next].clone(), environment),
+            _ => {
+                let node = body[next].clone();
+                self.push(Frame::Sequence(body, next + 1, environment.clone()), span)?;
+                State::Eval(node, environment)
+            },
+        })
+    }
+
+    fn execute_and(&mut self, tests: Body, next: usize, environment: Environment, span: Span) -> Result<State> {
+        let node = tests[next].clone();
+        if next + 1 < tests.len() {
+            self.push(Frame::And(tests, next + 1, environment.clone()), span)?;
+        }
+        Ok(State::Eval(node, environment))
+    }
+
+    fn execute_or(&mut self, tests: Body, next: usize, environment: Environment, span: Span) -> Result<State> {
+        let node = tests[next].clone();
+        if next + 1 < tests.len() {
+            self.push(Frame::Or(tests, next + 1, environment.clone()), span)?;
+        }
+        Ok(State::Eval(node, environment))
+    }
+
+    // Evaluates the test of the `guard` clause `i`, or re-raises when none
+    // is left.
+    fn execute_guard_clause(&mut self, node: Node, i: usize, environment: Environment, reraise: Rc<Continuation>) -> Result<State> {
+        let span = node.span;
+        let test = match &node.data {
+            Expression::Guard(_, clauses, _) => clauses.get(i).map(|clause| clause.test.clone()),
+            _ => unreachable!(),
+        };
+        match test {
+            Some(test) => {
+                self.push(Frame::GuardClause(node, i, environment.clone(), reraise), span)?;
+                Ok(State::Eval(test, environment))
+            },
+            None => self.reenter(reraise, Value::Unspecified, span),
+        }
+    }
+
+    fn execute_clause(
+        &mut self,
+        body: &ClauseBody,
+        test: Value,
+        environment: Environment,
+        span: Span,
+    ) -> Result<State> {
+        match body {
+            ClauseBody::Sequence(body) if body.is_empty() => Ok(State::Return(test)),
+            ClauseBody::Sequence(body) => self.execute_sequence(body.clone(), 0, environment, span),
+            ClauseBody::Receiver(receiver) => {
+                self.push(Frame::Receiver(test, span), span)?;
+                Ok(State::Eval(receiver.clone(), environment))
+            },
+        }
+    }
+
+    fn apply(&mut self, procedure: Value, arguments: Vec<Value>, span: Span) -> Result<State> {
+        let procedure = match procedure {
+            Value::Procedure(procedure) => procedure,
+            procedure => return Err(RuntimeError::NotProcedure(procedure).with_span(span)),
+        };
+        if !procedure.arity().accepts(arguments.len()) {
+            return Err(RuntimeError::WrongArgumentCount(procedure.arity(), arguments.len()).with_span(span));
+        }
+
+        let closure = match procedure {
+            Procedure::Closure(closure) => closure,
+            Procedure::Native(native) => {
+                return native.call(&arguments).map(State::Return).map_err(|e| e.with_span(span))
+            },
+            Procedure::Control(control) => return self.apply_control(control, arguments, span),
+            Procedure::Continuation(continuation) => {
+                return self.reenter(continuation, Value::values(arguments), span)
+            },
+        };
+        let lambda = &closure.lambda;
+
+        let environment = closure.environment.extend();
+        let mut arguments = arguments.into_iter();
+        for parameter in lambda.parameters.iter() {
+            environment.define(parameter, arguments.next().unwrap());
+        }
+        if let Some(rest) = &lambda.rest {
+            environment.define(rest, Value::list(arguments.collect::<Vec<_>>()));
+        }
+        self.execute_sequence(lambda.body.clone(), 0, environment, span)
+    }
+
+    fn apply_control(&mut self, control: Control, mut arguments: Vec<Value>, span: Span) -> Result<State> {
+        match control {
+            Control::Apply => {
+                let last = arguments.pop().unwrap();
+                match last.list_to_vec() {
+                    Some(rest) => arguments.extend(rest),
+                    None => return Err(RuntimeError::WrongType(arguments.len(), "list").with_span(span)),
+                }
+                let procedure = arguments.remove(0);
+                self.apply(procedure, arguments, span)
+            },
+            Control::CallWithCurrentContinuation => {
+                let continuation = Value::Procedure(Procedure::Continuation(Rc::new(Continuation {
+                    stack: self.stack.clone(),
+                    winders: self.winders.clone(),
+                    handlers: self.handlers.clone(),
+                })));
+                let receiver = arguments.pop().unwrap();
+                self.apply(receiver, vec![continuation], span)
+            },
+            Control::CallWithValues => {
+                let consumer = arguments.pop().unwrap();
+                let producer = arguments.pop().unwrap();
+                self.push(Frame::Consumer(consumer, span), span)?;
+                self.apply(producer, vec![], span)
+            },
+            Control::DynamicWind => {
+                let after = arguments.pop().unwrap();
+                let thunk = arguments.pop().unwrap();
+                let before = arguments.pop().unwrap();
+                self.push(Frame::WindBefore(before.clone(), thunk, after, span), span)?;
+                self.apply(before, vec![], span)
+            },
+            Control::WithExceptionHandler => {
+                let thunk = arguments.pop().unwrap();
+                let handler = arguments.pop().unwrap();
+                if !matches!(handler, Value::Procedure(_)) {
+                    return Err(RuntimeError::WrongType(1, "procedure").with_span(span));
+                }
+                self.push(Frame::Handlers(self.handlers.clone()), span)?;
+                self.handlers = Some(Rc::new(Handler {
+                    kind: HandlerKind::Procedure(handler),
+                    outer: self.handlers.take(),
+                }));
+                self.apply(thunk, vec![], span)
+            },
+            Control::RaiseContinuable => {
+                let condition = arguments.pop().unwrap();
+                self.raise(condition, true, span)
+            },
+        }
+    }
+
+    // Passes an error to the Scheme handlers, if any are installed, as an
+    // error object; objects raised by natives are passed as they are.
+    fn signal(&mut self, error: Located<RuntimeError>) -> Result<State> {
+        if self.handlers.is_none() {
+            return Err(error);
+        }
+        let span = error.span;
+        let condition = match error.data {
+            RuntimeError::Raised(condition) => condition,
+            _ => Value::Error(Rc::new(ErrorObject::from(error))),
+        };
+        self.raise(condition, false, span)
+    }
+
+    // Calls the current handler with `condition` in the dynamic environment
+    // of the raise, minus the handler itself. Unless `continuable`, a handler
+    // that returns is an error.
+    fn raise(&mut self, condition: Value, continuable: bool, span: Span) -> Result<State> {
+        let handler = match self.handlers.take() {
+            Some(handler) => handler,
+            None => return Err(uncaught(condition, span)),
+        };
+        let frame = match continuable {
+            true => Frame::Handlers(Some(handler.clone())),
+            false => Frame::Raised(condition.clone(), span),
+        };
+        self.push(frame, span)?;
+        self.handlers = handler.outer.clone();
+
+        match &handler.kind {
+            HandlerKind::Procedure(procedure) => self.apply(procedure.clone(), vec![condition], span),
+            HandlerKind::Guard(node, environment, height, winders) => {
+                let mut stack = self.stack.clone();
+                stack.push(Frame::Reraise(condition.clone(), span));
+                let reraise = Rc::new(Continuation {
+                    stack,
+                    winders: self.winders.clone(),
+                    handlers: self.handlers.clone(),
+                });
+                let mut stack = self.stack[..*height].to_vec();
+                stack.push(Frame::Guard(node.clone(), environment.clone(), reraise));
+                let guard = Rc::new(Continuation {
+                    stack,
+                    winders: winders.clone(),
+                    handlers: self.handlers.clone(),
+                });
+                self.reenter(guard, condition, span)
+            },
+        }
+    }
+
+    // Passes `value` to `continuation`, first leaving the dynamic-wind
+    // extents that it is not in and then entering the ones it is in.
+    fn reenter(&mut self, continuation: Rc<Continuation>, value: Value, span: Span) -> Result<State> {
+        let mut leaving = self.winders.clone();
+        let mut entering = continuation.winders.clone();
+        let mut befores = vec![];
+        let mut afters = vec![];
+        while depth(&leaving) > depth(&entering) {
+            let wind = leaving.unwrap();
+            afters.push((wind.after.clone(), wind.outer.clone()));
+            leaving = wind.outer.clone();
+        }
+        while depth(&entering) > depth(&leaving) {
+            let wind = entering.unwrap();
+            befores.push((wind.before.clone(), wind.outer.clone()));
+            entering = wind.outer.clone();
+        }
+        while let (Some(left), Some(entered)) = (&leaving, &entering) {
+            if Rc::ptr_eq(left, entered) {
+                break;
+            }
+            afters.push((left.after.clone(), left.outer.clone()));
+            befores.push((entered.before.clone(), entered.outer.clone()));
+            leaving = left.outer.clone();
+            entering = entered.outer.clone();
+        }
+
+        // `afters` run innermost first and `befores` outermost first; the
+        // steps are popped from the end
+        let steps = befores.into_iter().chain(afters.into_iter().rev()).collect();
+        self.rewind(steps, continuation, value, span)
+    }
+
+    fn rewind(
+        &mut self,
+        mut steps: Vec<(Value, Winders)>,
+        continuation: Rc<Continuation>,
+        value: Value,
+        span: Span,
+    ) -> Result<State> {
+        match steps.pop() {
+            Some((thunk, winders)) => {
+                self.winders = winders;
+                self.push(Frame::Rewind(steps, continuation, value, span), span)?;
+                self.apply(thunk, vec![], span)
+            },
+            None => {
+                self.stack = continuation.stack.clone();
+                self.winders = continuation.winders.clone();
+                self.handlers = continuation.handlers.clone();
+                Ok(State::Return(value))
+            },
+        }
+    }
+}
+
+// The error returned to the host for a condition no handler caught: errors
+// signalled by the interpreter come back as they were.
+fn uncaught(condition: Value, span: Span) -> Located<RuntimeError> {
+    if let Value::Error(error) = &condition {
+        if let ErrorKind::Runtime(error) = &error.kind {
+            return error.clone();
         }
     }
+    RuntimeError::Raised(condition).with_span(span)
+}
+
+// A span within the first line of the text.
+#[cfg(test)]
+fn span(start: usize, end: usize) -> Span {
+    let at = |offset: usize| Location{col: offset as u32, offset, ..Location::default()};
+    Span{start: at(start), end: at(end)}
+}
+
+#[cfg(test)]
+fn int(i: i64) -> Value {
+    Value::Number(super::Complex::Real(super::Real::Integer(i)))
+}
+
+#[cfg(test)]
+fn symbol(s: &str) -> Value {
+    Value::Symbol(s.to_string())
+}
+
+#[test]
+fn quote() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("'a")?, symbol("a"));
+    assert_eq!(interpreter.eval_str("(quote (1 . 2))")?, Value::cons(int(1), int(2)));
+    assert_eq!(interpreter.eval_str("'()")?, Value::Null);
+    assert_eq!(interpreter.eval_str("''a")?, Value::list(vec![symbol("quote"), symbol("a")]));
+    assert_eq!(interpreter.eval_str("\"abc\"")?, Value::string("abc"));
+    assert_eq!(interpreter.eval_str("#(1 #t)")?, Value::vector(vec![int(1), Value::Boolean(true)]));
+    Ok(())
+}
+
+#[test]
+fn conditionals() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("(if #t 1 2)")?, int(1));
+    assert_eq!(interpreter.eval_str("(if #f 1 2)")?, int(2));
+    assert_eq!(interpreter.eval_str("(if '() 1 2)")?, int(1));
+    assert_eq!(interpreter.eval_str("(if #f #f)")?, Value::Unspecified);
+
+    assert_eq!(interpreter.eval_str("(cond (#f 1) ((if #f #f 2) 3) (else 4))")?, int(3));
+    assert_eq!(interpreter.eval_str("(cond (#f 1) (else 2 4))")?, int(4));
+    assert_eq!(interpreter.eval_str("(cond (#f 1) (5))")?, int(5));
+    assert_eq!(interpreter.eval_str("(cond (7 => (lambda (x) x)))")?, int(7));
+
+    assert_eq!(interpreter.eval_str("(case 3 ((1 2) 'low) ((3 4) 'high) (else 'none))")?, symbol("high"));
+    assert_eq!(interpreter.eval_str("(case 'x ((a) 1) (else => (lambda (x) x)))")?, symbol("x"));
+    assert_eq!(interpreter.eval_str("(case #\\a ((#\\b) 1))")?, Value::Unspecified);
+
+    assert_eq!(interpreter.eval_str("(and)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(and 1 2)")?, int(2));
+    assert_eq!(interpreter.eval_str("(and 1 #f undefined)")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(or)")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(or #f 2 undefined)")?, int(2));
+
+    assert_eq!(interpreter.eval_str("(when #t 1 2)")?, int(2));
+    assert_eq!(interpreter.eval_str("(when #f 1)")?, Value::Unspecified);
+    assert_eq!(interpreter.eval_str("(unless #f 1 2)")?, int(2));
+    assert_eq!(interpreter.eval_str("(unless #t 1)")?, Value::Unspecified);
+    Ok(())
+}
+
+#[test]
+fn definitions_and_assignment() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("(define x 1) (define (id y) y) (define (tail a . rest) rest)")?;
+    assert_eq!(interpreter.eval_str("x")?, int(1));
+    assert_eq!(interpreter.eval_str("(set! x 2) x")?, int(2));
+    assert_eq!(interpreter.eval_str("(id 'a)")?, symbol("a"));
+    assert_eq!(interpreter.eval_str("(tail 1 2 3)")?, Value::list(vec![int(2), int(3)]));
+    assert_eq!(interpreter.eval_str("((lambda args args))")?, Value::Null);
+    assert_eq!(interpreter.eval_str("(begin (define z 5) z)")?, int(5));
+    Ok(())
+}
 
-    pub fn eval(&mut self, exp: &Expression) {
-        
+#[test]
+fn closures() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("
+        (define (make-counter)
+          (define count 'zero)
+          (lambda ()
+            (set! count (case count ((zero) 'one) ((one) 'two) (else 'many)))
+            count))
+        (define a (make-counter))
+        (define b (make-counter))
+    ")?;
+    assert_eq!(interpreter.eval_str("(a)")?, symbol("one"));
+    assert_eq!(interpreter.eval_str("(a)")?, symbol("two"));
+    assert_eq!(interpreter.eval_str("(b)")?, symbol("one"));
+    Ok(())
+}
+
+#[test]
+fn binding_forms() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("(let ((x 1) (y 2)) y)")?, int(2));
+    assert_eq!(interpreter.eval_str("(let ((x 1)) (let ((x 2) (y x)) y))")?, int(1));
+    assert_eq!(interpreter.eval_str("(let ((x 1)) (let* ((x 2) (y x)) y))")?, int(2));
+    assert_eq!(interpreter.eval_str("(let* () (define x 3) x)")?, int(3));
+    assert_eq!(interpreter.eval_str("
+        (letrec ((even? (lambda (n) (if (zero? n) #t (odd? (pred n)))))
+                 (odd? (lambda (n) (if (zero? n) #f (even? (pred n)))))
+                 (zero? (lambda (n) (case n ((0) #t) (else #f))))
+                 (pred (lambda (n) (case n ((3) 2) ((2) 1) ((1) 0)))))
+          (even? 3))
+    ")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("
+        (let loop ((i 3) (last #f))
+          (case i
+            ((0) last)
+            (else (loop (case i ((3) 2) ((2) 1) ((1) 0)) i))))
+    ")?, int(1));
+    Ok(())
+}
+
+#[test]
+fn mutable_data() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("
+        (define p (list 1 2 3))
+        (define alias p)
+        (set-car! (cdr p) 'two)
+        (set-cdr! (cddr p) '(4))
+        (define s (make-string 3 #\\a))
+        (string-set! s 1 #\\b)
+        (define v (vector 1 2))
+        (vector-set! v 0 s)
+    ")?;
+    assert_eq!(interpreter.eval_str("alias")?, Value::list(vec![int(1), symbol("two"), int(3), int(4)]));
+    assert_eq!(interpreter.eval_str("(eq? p alias)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(let ((x 100)) (eq? x x))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(eqv? 0.0 -0.0)")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(eqv? 0.0 0.0)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(= 0.0 -0.0)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(eqv? 2 2.0)")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("s")?, Value::string("aba"));
+    assert_eq!(interpreter.eval_str("(string-set! s 0 #\\z) (vector-ref v 0)")?, Value::string("zba"));
+    assert_eq!(interpreter.eval_str("(set-car! '() 1)").unwrap_err().data, RuntimeError::WrongType(0, "pair"));
+    assert_eq!(interpreter.eval_str("(vector-ref v 2)").unwrap_err().data, RuntimeError::IndexOutOfRange(2));
+    Ok(())
+}
+
+#[test]
+fn ports_and_special_objects() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("
+        (define in (open-input-string \"ab\"))
+        (define out (open-output-string))
+        (write-char (read-char in) out)
+        (write-string \"-\" out)
+        (write-char (peek-char in) out)
+    ")?;
+    assert_eq!(interpreter.eval_str("(get-output-string out)")?, Value::string("a-b"));
+    assert_eq!(interpreter.eval_str("(read-char in) (eof-object? (read-char in))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(values 1 2)")?, Value::values(vec![int(1), int(2)]));
+    assert_eq!(interpreter.eval_str("(values 1)")?, int(1));
+    assert_eq!(interpreter.eval_str("(procedure? car)")?, Value::Boolean(true));
+    Ok(())
+}
+
+#[test]
+fn host_environment() -> Result<()> {
+    let environment = Environment::new();
+    environment.define("answer", int(42));
+    let mut interpreter = Interpreter::with_environment(environment.clone());
+
+    assert_eq!(interpreter.eval_str("answer")?, int(42));
+    interpreter.eval_str("(define (f answer) (set! answer 'local) answer) (f 1)")?;
+    assert_eq!(environment.lookup("answer"), Ok(int(42)));
+    interpreter.eval_str("(set! answer 'global) (define added #t)")?;
+    assert_eq!(environment.lookup("answer"), Ok(symbol("global")));
+    assert!(interpreter.environment().is_bound("added"));
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval_str("\n  (undefined)").unwrap_err(),
+        RuntimeError::UnboundVariable("undefined".to_string()).with_span(Span{
+            start: Location{row: 1, col: 3, offset: 4, ..Location::default()},
+            end: Location{row: 1, col: 12, offset: 13, ..Location::default()},
+        })
+    );
+    assert_eq!(interpreter.eval_str("\n  (undefined)").unwrap_err().to_string(), "2:4: unbound variable `undefined`");
+    assert_eq!(
+        interpreter.eval_str("(car 1 2)").unwrap_err().data.to_string(),
+        "wrong number of arguments: expected 1, got 2"
+    );
+    assert_eq!(interpreter.eval_str("(error \"bad thing:\" 1 'x)").unwrap_err().data.to_string(), "bad thing: 1 x");
+    assert_eq!(
+        interpreter.eval_str("(set! undefined 1)").unwrap_err().data,
+        RuntimeError::UndefinedAssignment("undefined".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_str("(1 2)").unwrap_err(),
+        RuntimeError::NotProcedure(int(1)).with_span(span(0, 5))
+    );
+    assert_eq!(
+        interpreter.eval_str("((lambda (x) x))").unwrap_err().data,
+        RuntimeError::WrongArgumentCount(Arity::Exactly(1), 0)
+    );
+    assert_eq!(
+        interpreter.eval_str("(if)").unwrap_err().data,
+        RuntimeError::BadSyntax("if".to_string())
+    );
+    assert_eq!(
+        interpreter.eval_str("(lambda (x x) x)").unwrap_err().data,
+        RuntimeError::BadSyntax("lambda".to_string())
+    );
+    for (text, keyword) in [("(let ((x 1) (x 2)) x)", "let"), ("(let f ((x 1) (x 2)) x)", "let"), ("(letrec ((f 1) (f 2)) f)", "letrec")] {
+        assert_eq!(interpreter.eval_str(text).unwrap_err().data, RuntimeError::BadSyntax(keyword.to_string()));
     }
-}
\ No newline at end of file
+    assert_eq!(interpreter.eval_str("(let* ((x 1) (x (if x 2))) x)"), Ok(int(2)));
+    assert_eq!(
+        interpreter.eval_str("(cond (else 1) (#t 2))").unwrap_err().data,
+        RuntimeError::BadSyntax("cond".to_string())
+    );
+}
+
+#[test]
+fn tail_calls() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stack_limit(100);
+    assert_eq!(interpreter.eval_str("(let loop ((i 0)) (if (< i 1000000) (loop (+ i 1)) i))")?, int(1000000));
+    interpreter.eval_str("
+        (define (even? n) (cond ((= n 0) #t) (else (odd? (- n 1)))))
+        (define (odd? n) (and (not (= n 0)) (even? (- n 1))))
+    ")?;
+    assert_eq!(interpreter.eval_str("(even? 100001)")?, Value::Boolean(false));
+    Ok(())
+}
+
+#[test]
+fn deep_recursion() {
+    // run on a small stack, as embedded workers do
+    std::thread::Builder::new().stack_size(256 * 1024).spawn(|| {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_str("
+            (define (fib n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))
+            (define (count n) (if (= n 0) '() (cons n (count (- n 1)))))
+            (define (sum l) (if (null? l) 0 (+ (car l) (sum (cdr l)))))
+        ").unwrap();
+        assert_eq!(interpreter.eval_str("(fib 20)"), Ok(int(6765)));
+        assert_eq!(interpreter.eval_str("(sum (count 100000))"), Ok(int(5000050000)));
+
+        interpreter.set_stack_limit(1000);
+        assert_eq!(
+            interpreter.eval_str("(sum (count 100000))").unwrap_err().data,
+            RuntimeError::RecursionLimitExceeded
+        );
+        assert_eq!(interpreter.eval_str("(sum (count 100))"), Ok(int(5050)));
+    }).unwrap().join().unwrap();
+}
+
+#[test]
+fn continuations() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("(+ 1 (call/cc (lambda (k) (+ 10 (k 2)))))")?, int(3));
+    assert_eq!(interpreter.eval_str("(call-with-current-continuation procedure?)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("
+        (define (find-first pred l)
+          (call/cc (lambda (return)
+            (let loop ((l l))
+              (when (pair? l)
+                (if (pred (car l)) (return (car l)))
+                (loop (cdr l))))
+            #f)))
+        (find-first (lambda (x) (> x 2)) '(1 2 3 4))
+    ")?, int(3));
+    assert_eq!(interpreter.eval_str("
+        (let ((k #f) (n 0))
+          (let ((v (call/cc (lambda (c) (set! k c) 0))))
+            (set! n (+ n 1))
+            (if (< v 3) (k (+ v 1)) (list v n))))
+    ")?, Value::list(vec![int(3), int(4)]));
+    assert_eq!(interpreter.eval_str("
+        (define (make-generator l)
+          (define return #f)
+          (define (resume)
+            (let loop ((l l))
+              (when (pair? l)
+                (call/cc (lambda (k)
+                  (set! resume (lambda () (k #f)))
+                  (return (car l))))
+                (loop (cdr l))))
+            (return 'done))
+          (lambda () (call/cc (lambda (r) (set! return r) (resume)))))
+        (define g (make-generator '(1 2)))
+        (let* ((a (g)) (b (g)) (c (g)) (d (g))) (list a b c d))
+    ")?, Value::list(vec![int(1), int(2), symbol("done"), symbol("done")]));
+    assert_eq!(interpreter.eval_str("(call-with-values (lambda () (values 1 2)) cons)")?, Value::cons(int(1), int(2)));
+    assert_eq!(interpreter.eval_str("(call-with-values (lambda () 1) list)")?, Value::list(vec![int(1)]));
+    assert_eq!(interpreter.eval_str("(apply + 1 2 '(3 4))")?, int(10));
+    assert_eq!(interpreter.eval_str("(apply + 1 2)").unwrap_err().data, RuntimeError::WrongType(2, "list"));
+    Ok(())
+}
+
+#[test]
+fn dynamic_wind() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("
+        (define trace '())
+        (define (note x) (set! trace (cons x trace)))
+        (define (traced name thunk)
+          (dynamic-wind (lambda () (note (list 'in name))) thunk (lambda () (note (list 'out name)))))
+    ")?;
+    assert_eq!(interpreter.eval_str("(traced 'a (lambda () 1))")?, int(1));
+    assert_eq!(interpreter.eval_str("
+        (set! trace '())
+        (call/cc (lambda (k) (traced 'a (lambda () (traced 'b (lambda () (k 2)))))))
+    ")?, int(2));
+    assert_eq!(interpreter.eval_str("(reverse trace)")?, interpreter.eval_str("'((in a) (in b) (out b) (out a))")?);
+
+    // re-entry runs the `before` thunks again, outermost first
+    assert_eq!(interpreter.eval_str("
+        (set! trace '())
+        (let ((k #f) (count 0))
+          (traced 'a (lambda ()
+            (traced 'b (lambda () (call/cc (lambda (c) (set! k c)))))))
+          (set! count (+ count 1))
+          (if (< count 2) (k 'again))
+          count)
+    ")?, int(2));
+    assert_eq!(
+        interpreter.eval_str("(reverse trace)")?,
+        interpreter.eval_str("'((in a) (in b) (out b) (out a) (in a) (in b) (out b) (out a))")?
+    );
+
+    // jumping between sibling extents leaves one and enters the other
+    assert_eq!(interpreter.eval_str("
+        (set! trace '())
+        (let ((k #f))
+          (traced 'a (lambda () (call/cc (lambda (c) (set! k c)))))
+          (if k
+            (traced 'b (lambda () (let ((c k)) (set! k #f) (c #f)))))
+          'done)
+    ")?, symbol("done"));
+    assert_eq!(
+        interpreter.eval_str("(reverse trace)")?,
+        interpreter.eval_str("'((in a) (out a) (in b) (out b) (in a) (out a))")?
+    );
+    Ok(())
+}
+
+#[test]
+fn exceptions() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("
+        (with-exception-handler
+          (lambda (c) 42)
+          (lambda () (+ (raise-continuable 'oops) 1)))
+    ")?, int(43));
+    assert_eq!(interpreter.eval_str("
+        (call/cc (lambda (k)
+          (with-exception-handler
+            (lambda (c) (k (list 'caught c)))
+            (lambda () (raise 'boom)))))
+    ")?, Value::list(vec![symbol("caught"), symbol("boom")]));
+
+    assert_eq!(interpreter.eval_str("(guard (e (#t (list 'caught e))) (raise 'boom))")?, Value::list(vec![symbol("caught"), symbol("boom")]));
+    assert_eq!(interpreter.eval_str("(guard (e ((symbol? e) 'symbol) ((string? e) 'string)) (raise \"x\"))")?, symbol("string"));
+    assert_eq!(interpreter.eval_str("(guard (e ((assq 'a e) => cdr) (else 'other)) (raise '((a . 1))))")?, int(1));
+    assert_eq!(interpreter.eval_str("(guard (e (else 'other)) 'fine)")?, symbol("fine"));
+    assert_eq!(
+        interpreter.eval_str("
+            (guard (e ((error-object? e) (cons (error-object-message e) (error-object-irritants e))))
+              (error \"bad thing\" 1 2))
+        ")?,
+        Value::list(vec![Value::string("bad thing"), int(1), int(2)])
+    );
+
+    // errors signalled by primitives and the evaluator are catchable
+    assert_eq!(interpreter.eval_str("(guard (e ((error-object? e) (error-object-message e))) (car 1))")?, Value::string("wrong type argument"));
+    assert_eq!(interpreter.eval_str("(guard (e (#t (error-object-irritants e))) undefined)")?, Value::list(vec![symbol("undefined")]));
+
+    // without a matching clause the condition goes to the outer handler
+    assert_eq!(interpreter.eval_str("
+        (with-exception-handler
+          (lambda (c) 10)
+          (lambda () (+ 1 (guard (e ((string? e) 0)) (raise-continuable 'x)))))
+    ")?, int(11));
+    assert_eq!(
+        interpreter.eval_str("(guard (outer (#t (list 'outer outer))) (guard (inner ((string? inner) 'inner)) (raise 'x)))")?,
+        Value::list(vec![symbol("outer"), symbol("x")])
+    );
+
+    // leaving the guard body runs the `after` thunks
+    assert_eq!(interpreter.eval_str("
+        (define trace '())
+        (guard (e (#t (set! trace (cons 'handled trace))))
+          (dynamic-wind
+            (lambda () (set! trace (cons 'in trace)))
+            (lambda () (raise 'x))
+            (lambda () (set! trace (cons 'out trace)))))
+        trace
+    ")?, Value::list(vec![symbol("handled"), symbol("out"), symbol("in")]));
+
+    // uncaught conditions reach the host
+    assert_eq!(interpreter.eval_str("(raise 'boom)").unwrap_err().data, RuntimeError::Raised(symbol("boom")));
+    assert_eq!(
+        interpreter.eval_str("(with-exception-handler (lambda (c) 0) (lambda () (raise 'boom)))").unwrap_err().data,
+        RuntimeError::HandlerReturned(symbol("boom"))
+    );
+    assert_eq!(
+        interpreter.eval_str("(guard (e ((string? e) 0)) (car 1))").unwrap_err(),
+        RuntimeError::WrongType(0, "pair").with_span(span(27, 34))
+    );
+    match interpreter.eval_str("(error \"failed\" 'x)").unwrap_err().data {
+        RuntimeError::Raised(Value::Error(error)) => {
+            assert_eq!(error.message, "failed");
+            assert_eq!(error.irritants, vec![symbol("x")]);
+        },
+        error => panic!("unexpected {:?}", error),
+    }
+    Ok(())
+}
+
+#[test]
+fn read() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("(define p (open-input-string \"(a . b) \\\"c\\\"\\n #(1) x\"))")?;
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::cons(symbol("a"), symbol("b")));
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::string("c"));
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::vector(vec![int(1)]));
+    assert_eq!(interpreter.eval_str("(read-char p)")?, Value::Character(' '));
+    assert_eq!(interpreter.eval_str("(read p)")?, symbol("x"));
+    assert_eq!(interpreter.eval_str("(eof-object? (read p))")?, Value::Boolean(true));
+
+    assert_eq!(
+        interpreter.eval_str("(guard (e ((read-error? e) (error-object-message e))) (read (open-input-string \"(1 2\")))")?,
+        Value::string("unexpected end of input")
+    );
+    assert_eq!(
+        interpreter.eval_str("(guard (e ((file-error? e) 'missing)) (open-input-file \"/nonexistent/file\"))")?,
+        symbol("missing")
+    );
+
+    // 2.4 datum labels
+    assert_eq!(interpreter.eval_str("(let ((x '#0=(a b . #0#))) (eq? x (cddr x)))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(let ((x '(#0=(x) #0#))) (eq? (car x) (cadr x)))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(let ((v '#0=#(1 #0#))) (eq? v (vector-ref v 1)))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(let ((x '#0=#1=(a . #1#))) (eq? x (cdr x)))")?, Value::Boolean(true));
+    // the scope of a label is the whole top-level datum, across quotes
+    assert_eq!(interpreter.eval_str("(list '#0=(a) '#0#)")?, Value::list(vec![Value::list(vec![symbol("a")]); 2]));
+    assert_eq!(interpreter.eval_str("(let ((x (list '#0=(a) '#0#))) (eq? (car x) (cadr x)))")?, Value::Boolean(true));
+    // a label on code names no object
+    assert_eq!(
+        interpreter.eval_str("(begin #0=(list 1) '#0#)"),
+        Err(RuntimeError::ProcessorError(super::ProcessorError::UndefinedLabel(0)).with_span(super::Span{
+            start: Location{col: 20, offset: 20, ..Location::default()},
+            end: Location{col: 23, offset: 23, ..Location::default()},
+        }))
+    );
+    // 6.1 equal? ends on circular structure
+    assert_eq!(interpreter.eval_str("(equal? '#0=(a . #0#) '#1=(a . #1#))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(equal? '#0=(a b . #0#) '#1=(a . #1#))")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(equal? '#0=(a b . #0#) '(a b . #1=(a b . #1#)))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(equal? '#0=#(1 #0#) '#1=#(1 #1#))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(equal? '#0=#(1 #0#) '#1=#(2 #1#))")?, Value::Boolean(false));
+    interpreter.eval_str("(define p (open-input-string \"#0=(1 . #0#) (#0#)\"))")?;
+    assert_eq!(interpreter.eval_str("(let ((x (read p))) (eq? x (cdr x)))")?, Value::Boolean(true));
+    assert_eq!(
+        interpreter.eval_str("(guard (e ((read-error? e) (error-object-message e))) (read p))")?,
+        Value::string("undefined datum label")
+    );
+    assert_eq!(
+        interpreter.eval_str("'#1=#1#"),
+        located_error!(RuntimeError::ProcessorError(super::ProcessorError::UndefinedLabel(1)), Location{row: 0, col: 1, offset: 1, ..Location::default()})
+    );
+    assert_eq!(
+        interpreter.eval_str("'#0=#1=#0#"),
+        located_error!(RuntimeError::ProcessorError(super::ProcessorError::UndefinedLabel(0)), Location{row: 0, col: 4, offset: 4, ..Location::default()})
+    );
+
+    // 2.1 a directive holds for the later reads from the port
+    interpreter.eval_str("(define p (open-input-string \"#!fold-case ABC DEF #!no-fold-case GHI\"))")?;
+    assert_eq!(interpreter.eval_str("(read p)")?, symbol("abc"));
+    assert_eq!(interpreter.eval_str("(read p)")?, symbol("def"));
+    assert_eq!(interpreter.eval_str("(read p)")?, symbol("GHI"));
+    assert_eq!(interpreter.eval_str("#!fold-case (eq? 'STRASSE 'straße)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("#!fold-case (eq? 'ΣΑΣ 'σας)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(eq? 'ΣΑΣ 'σας)")?, Value::Boolean(false));
+
+    // 2.2 datum comments
+    interpreter.eval_str("(define p (open-input-string \"#;a #;(b c) d (1 #;2 . #;3 4 #;5) #(#;#;1 2 3) #u8(#;1 2) '#;x y #;z\"))")?;
+    assert_eq!(interpreter.eval_str("(read p)")?, symbol("d"));
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::cons(int(1), int(4)));
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::vector(vec![int(3)]));
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::bytevector(vec![2]));
+    assert_eq!(interpreter.eval_str("(read p)")?, Value::list(vec![symbol("quote"), symbol("y")]));
+    assert_eq!(interpreter.eval_str("(eof-object? (read p))")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(+ 1 #;(car '()) 2)")?, int(3));
+    assert_eq!(
+        interpreter.eval_str("(guard (e ((read-error? e) (error-object-message e))) (read (open-input-string \"(1 #;)\")))")?,
+        Value::string("unmatched parenthesis")
+    );
+
+    interpreter.eval_str("(define p (open-input-string \"1\\n  )\")) (read p)")?;
+    match interpreter.eval_str("(read p)").unwrap_err().data {
+        RuntimeError::Raised(Value::Error(error)) => match &error.kind {
+            ErrorKind::Read(error) => assert_eq!(
+                *error,
+                super::ProcessorError::UnmatchedParentheses.with_location(Location{row: 1, col: 2, offset: 4, ..Location::default()})
+            ),
+            _ => panic!("not a read error"),
+        },
+        error => panic!("unexpected {:?}", error),
+    }
+    Ok(())
+}
+
+#[test]
+fn native_procedures() -> Result<()> {
+    use std::cell::RefCell;
+
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut interpreter = Interpreter::new();
+    let messages = log.clone();
+    interpreter.define_native("log!", Arity::AtLeast(1), move |args| {
+        let mut message = String::new();
+        for (i, arg) in args.iter().enumerate() {
+            match arg {
+                Value::String(s) => message.push_str(&s.borrow()),
+                Value::Symbol(s) => message.push_str(s),
+                _ => return Err(RuntimeError::WrongType(i, "string or symbol")),
+            }
+        }
+        messages.borrow_mut().push(message);
+        Ok(Value::Unspecified)
+    });
+    interpreter.define_native("config", Arity::Between(1, 2), |args| match &args[0] {
+        Value::Symbol(key) if key == "threads" => Ok(int(4)),
+        Value::Symbol(_) => Ok(args.get(1).cloned().unwrap_or(Value::Boolean(false))),
+        _ => Err(RuntimeError::WrongType(0, "symbol")),
+    });
+
+    assert_eq!(interpreter.eval_str("(log! \"started \" 'worker)")?, Value::Unspecified);
+    assert_eq!(*log.borrow(), vec!["started worker".to_string()]);
+    assert_eq!(interpreter.eval_str("(config 'threads)")?, int(4));
+    assert_eq!(interpreter.eval_str("(config 'missing)")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(config 'missing 0)")?, int(0));
+    assert_eq!(interpreter.eval_str("(apply config '(missing 1))")?, int(1));
+
+    assert_eq!(
+        interpreter.eval_str("(config)").unwrap_err().data,
+        RuntimeError::WrongArgumentCount(Arity::Between(1, 2), 0)
+    );
+    assert_eq!(
+        interpreter.eval_str("(define (f) (log! 'a 1))\n(f)").unwrap_err(),
+        RuntimeError::WrongType(1, "string or symbol").with_span(span(12, 23))
+    );
+    assert_eq!(interpreter.eval_str("(guard (e (#t (error-object-irritants e))) (config 1))")?, Value::list(vec![int(0), Value::string("symbol")]));
+    Ok(())
+}
+
+#[test]
+fn typed_functions() -> Result<()> {
+    use std::collections::HashMap;
+
+    let mut interpreter = Interpreter::new();
+    interpreter.define_function("scale", |factor: f64, values: Vec<i64>| {
+        Ok(values.into_iter().map(|v| v as f64 * factor).collect::<Vec<_>>())
+    });
+    interpreter.define_function("lookup", |table: HashMap<String, i64>, key: String, default: Option<i64>| {
+        Ok(table.get(&key).copied().or(default))
+    });
+    interpreter.define_function("greet", |name: String, times: usize| Ok(name.repeat(times)));
+    interpreter.define_function("nothing", || Ok(()));
+
+    assert_eq!(interpreter.eval_str("(scale 2 '(1 2))")?, interpreter.eval_str("'(2. 4.)")?);
+    assert_eq!(interpreter.eval_str("(lookup '((\"a\" . 1)) \"a\" #f)")?, int(1));
+    assert_eq!(interpreter.eval_str("(lookup '() \"a\" #f)")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(greet \"ab\" 2)")?, Value::string("abab"));
+    assert_eq!(interpreter.eval_str("(nothing)")?, Value::Unspecified);
+
+    assert_eq!(
+        interpreter.eval_str("(greet \"ab\" -1)").unwrap_err(),
+        RuntimeError::WrongType(1, "exact integer (usize)").with_span(span(0, 15))
+    );
+    assert_eq!(interpreter.eval_str("(scale 2 '(1 x))").unwrap_err().data, RuntimeError::WrongType(1, "list"));
+    assert_eq!(
+        interpreter.eval_str("(greet \"ab\")").unwrap_err().data,
+        RuntimeError::WrongArgumentCount(Arity::Exactly(2), 1)
+    );
+    Ok(())
+}
+
+#[test]
+fn exact_numbers() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    let mut eval = |text: &str| interpreter.eval_str(text).map(|value| value.to_string());
+    assert_eq!(eval("(* 99999999999 99999999999 99999999999)")?, "999999999970000000000299999999999");
+    assert_eq!(eval("(- (+ 9223372036854775807 1) 1)")?, "9223372036854775807");
+    assert_eq!(eval("(- -9223372036854775808)")?, "9223372036854775808");
+    assert_eq!(eval("(let loop ((i 0) (acc 1)) (if (= i 30) acc (loop (+ i 1) (* acc 1000))))")?, format!("1{}", "0".repeat(90)));
+    assert_eq!(eval("(/ 1 3)")?, "1/3");
+    assert_eq!(eval("(+ 1/3 2/3)")?, "1");
+    assert_eq!(eval("(* 4/3 -3/8)")?, "-1/2");
+    assert_eq!(eval("(/ 6 4 -1)")?, "-3/2");
+    assert_eq!(eval("(/ 2)")?, "1/2");
+    assert_eq!(eval("(+ 1/2 0.5)")?, "1.0");
+    assert_eq!(eval("(/ 1.0 0)")?, "+inf.0");
+    assert_eq!(eval("(list (< 1/3 0.34 12345678901234567890) (= 1/2 0.5) (> 1/2 1/3) (integer? 100000000000000000000))")?, "(#t #t #t #t)");
+    assert_eq!(eval("(list (exact? 1/2) (inexact? 0.5) (rational? 1/2) (rational? +inf.0) (zero? 0/7))")?, "(#t #t #t #f #t)");
+    assert_eq!(eval("(list (exact? #e1.5) (inexact? #i3) (exact? 1e3) (+ #e0.1 #e0.2))")?, "(#t #t #f 3/10)");
+    assert_eq!(interpreter.eval_str("(/ 1 0)"), Err(RuntimeError::DivisionByZero.with_span(span(0, 7))));
+    Ok(())
+}
+
+#[test]
+fn numeric_tower() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    let mut eval = |text: &str| interpreter.eval_str(text).map(|value| value.to_string());
+    // contagion, infinities and NaNs
+    assert_eq!(eval("(list (+ 1 2.0) (* 1/2 4) (- 1/2 0.5) (+ +inf.0 1) (+ +inf.0 -inf.0) (* 0 +inf.0))")?, "(3.0 2 0.0 +inf.0 +nan.0 +nan.0)");
+    assert_eq!(eval("(list (/ 0.0 0) (/ -1 0.0) (< 1 +nan.0) (= +nan.0 +nan.0) (max 1 +nan.0) (nan? (- +nan.0)))")?, "(+nan.0 -inf.0 #f #f +nan.0 #t)");
+    assert_eq!(eval("(list (max 1 2.0) (min 1 2.0) (max 1/2 1/3) (min -inf.0 0) (abs -1/2) (abs -0.0))")?, "(2.0 1.0 1/2 -inf.0 1/2 0.0)");
+    assert_eq!(eval("(list (= 1 1.0 1) (< 1/3 0.3333333333333333) (< 1 2 2) (<= 1 2 2) (> 3 2 1) (>= 1 1.0))")?, "(#t #f #f #t #t #t)");
+    assert_eq!(eval("(list (< 9007199254740992 9007199254740993.0 9007199254740993))")?, "(#f)");
+    // complex arithmetic
+    assert_eq!(eval("(list (+ 1+2i 3-2i) (* +i +i) (* 1+2i 3+4i) (/ 1+i 2) (/ 5 1+2i) (- +i) (= 1+0.0i 1))")?, "(4 -1 -5+10i 1/2+1/2i 1-2i -i #t)");
+    assert_eq!(eval("(list (zero? 0+0.0i) (real? 1+0i) (complex? 1+i) (exact? 1/2+i) (infinite? 1+inf.0i) (finite? 1+2i))")?, "(#t #t #t #t #t #t)");
+    // integer division
+    assert_eq!(eval("(call-with-values (lambda () (floor/ -7 2)) list)")?, "(-4 1)");
+    assert_eq!(eval("(call-with-values (lambda () (truncate/ -7 2)) list)")?, "(-3 -1)");
+    assert_eq!(eval("(list (quotient 17 -5) (remainder 17 -5) (modulo 17 -5) (modulo -7 2.0) (floor-quotient 7 -2) (truncate-remainder -7 2))")?, "(-3 2 -3 1.0 -4 -1)");
+    assert_eq!(eval("(list (gcd 32 -36) (gcd) (lcm 32 -36) (lcm 32.0 -36) (lcm) (odd? 3) (even? -4) (even? 100000000000000000001))")?, "(4 0 288 288.0 1 #t #t #f)");
+    // rationals and rounding
+    assert_eq!(eval("(list (numerator 6/4) (denominator 6/4) (denominator 0) (numerator 0.5) (denominator 0.5))")?, "(3 2 1 1.0 2.0)");
+    assert_eq!(eval("(list (floor -4.3) (ceiling -4.3) (truncate -4.3) (round -4.3) (floor 3.5) (ceiling 3.5) (truncate 3.5) (round 3.5))")?, "(-5.0 -4.0 -4.0 -4.0 3.0 4.0 3.0 4.0)");
+    assert_eq!(eval("(list (round 7/2) (round 5/2) (round -5/2) (round 2.5) (floor -7/2) (ceiling -7/2) (truncate -7/2) (round 7))")?, "(4 2 -2 2.0 -4 -3 -3 7)");
+    // powers and exactness
+    assert_eq!(eval("(list (expt 2 100) (expt 2/3 -2) (expt 2 0.5) (expt 4 1/2) (expt 0 0) (expt 0.0 0) (expt 0 2.5) (expt +i 3) (expt 2.0 -1))")?,
+        "(1267650600228229401496703205376 9/4 1.4142135623730951 2.0 1 1 0.0 -i 0.5)");
+    assert_eq!(eval("(list (exact? (expt -8 1/3)) (real? (expt -1 0.5)) (expt -2 3.0))")?, "(#f #f -8.0)");
+    assert_eq!(eval("(call-with-values (lambda () (exact-integer-sqrt 17)) list)")?, "(4 1)");
+    assert_eq!(eval("(call-with-values (lambda () (exact-integer-sqrt 100000000000000000000)) list)")?, "(10000000000 0)");
+    assert_eq!(eval("(list (exact 2.5) (exact 0.1) (inexact 1/3) (exact->inexact 1/4) (inexact->exact 4.0) (exact 1.5+2.5i) (inexact 1/2+i))")?,
+        "(5/2 3602879701896397/36028797018963968 0.3333333333333333 0.25 4 3/2+5/2i 0.5+1.0i)");
+    assert_eq!(eval("(list (square 1/2) (square 1.5) (exact-integer? 5) (exact-integer? 5.0) (rational? 1.5) (integer? 2.0) (positive? -0.0) (negative? -1/2))")?,
+        "(1/4 2.25 #t #f #t #t #f #t)");
+    // an exact power may be as large as it needs when its base stays small
+    assert_eq!(eval("(list (expt 1 (expt 10 10)) (expt -1 (+ (expt 10 10) 1)) (expt 0 (expt 10 10)) (expt -i (expt 10 10)))")?, "(1 -1 0 1)");
+    assert_eq!(eval("(list (expt 10. 99999999) (exact-integer? (expt 7 100000)))")?, "(+inf.0 #t)");
+
+    assert_eq!(interpreter.eval_str("(modulo 1 0)"), Err(RuntimeError::DivisionByZero.with_span(span(0, 12))));
+    assert_eq!(interpreter.eval_str("(expt 0 -1)"), Err(RuntimeError::DivisionByZero.with_span(span(0, 11))));
+    // exact powers are bounded in size
+    assert_eq!(interpreter.eval_str("(expt 10 99999999)"), Err(RuntimeError::NumberTooLarge.with_span(span(0, 18))));
+    assert_eq!(interpreter.eval_str("(expt 2 (expt 10 10))"), Err(RuntimeError::NumberTooLarge.with_span(span(0, 21))));
+    assert_eq!(interpreter.eval_str("(expt 1/2 -99999999)"), Err(RuntimeError::NumberTooLarge.with_span(span(0, 20))));
+    assert_eq!(interpreter.eval_str("(expt 1/2+i 100000)"), Err(RuntimeError::NumberTooLarge.with_span(span(0, 19))));
+    assert_eq!(interpreter.eval_str("(quotient 1.5 1)"), Err(RuntimeError::WrongType(0, "integer").with_span(span(0, 16))));
+    assert_eq!(interpreter.eval_str("(exact +inf.0)"), Err(RuntimeError::WrongType(0, "finite number").with_span(span(0, 14))));
+    assert_eq!(interpreter.eval_str("(eqv? 1e400 +inf.0)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(eqv? (string->number \"-1e400\") -inf.0)")?, Value::Boolean(true));
+    assert_eq!(interpreter.eval_str("(string->number \"#e1e99999999\")")?, Value::Boolean(false));
+    assert_eq!(interpreter.eval_str("(< 1 +i)"), Err(RuntimeError::WrongType(1, "real number").with_span(span(0, 8))));
+    Ok(())
+}
+
+#[test]
+fn complex_math() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    let mut eval = |text: &str| interpreter.eval_str(text).map(|value| value.to_string());
+    // polar literals and the (scheme complex) procedures
+    assert_eq!(eval("(list 2@0 1@0.0 (= 2@1 (make-polar 2 1)) (make-polar 2 0) (make-rectangular 1 2) (make-rectangular 1 0))")?, "(2 1.0+0.0i #t 2 1+2i 1)");
+    assert_eq!(eval("(list (real-part 1+2i) (imag-part 1+2i) (imag-part 1.5) (real-part +2i) (magnitude 3+4i) (magnitude -5) (magnitude 1+i))")?,
+        "(1 2 0 0 5 5 1.4142135623730951)");
+    assert_eq!(eval("(list (angle 1) (angle -1) (angle +i) (angle -1.0) (< (abs (- (angle 2@1) 1)) 1e-15) (< (abs (- (magnitude 2@1) 2)) 1e-15))")?,
+        "(0 3.141592653589793 1.5707963267948966 3.141592653589793 #t #t)");
+    // transcendental functions
+    assert_eq!(eval("(list (sqrt 16) (sqrt 1/4) (sqrt -4) (sqrt -4.0) (sqrt 2) (sqrt -3+4i) (sqrt +2i) (sqrt 2.25))")?,
+        "(4 1/2 +2i +2.0i 1.4142135623730951 1+2i 1+i 1.5)");
+    assert_eq!(eval("(list (exp 0) (exp 1) (log 1) (log 100 10) (log -1) (log 0.0) (real-part (exp +3.141592653589793i)))")?,
+        "(1 2.718281828459045 0 2.0 0.0+3.141592653589793i -inf.0 -1.0)");
+    assert_eq!(eval("(list (sin 0) (cos 0) (tan 0) (asin 0) (acos 1) (atan 0) (asin 1) (atan 1 1) (atan 0 1) (atan 1 0))")?,
+        "(0 1 0 0 0 0 1.5707963267948966 0.7853981633974483 0 1.5707963267948966)");
+    assert_eq!(eval("(list (sin +i) (acos 2) (real? (asin 2)) (real? (atan 2)) (< (magnitude (- (tan 1+i) (/ (sin 1+i) (cos 1+i)))) 1e-15))")?,
+        "(0.0+1.1752011936438014i 0.0+1.3169578969248166i #f #t #t)");
+
+    assert_eq!(interpreter.eval_str("(log 1 1)"), Err(RuntimeError::DivisionByZero.with_span(span(0, 9))));
+    assert_eq!(interpreter.eval_str("(make-polar 1 +i)"), Err(RuntimeError::WrongType(1, "real number").with_span(span(0, 17))));
+    Ok(())
+}
+
+#[test]
+fn number_conversion() -> Result<()> {
+    let mut interpreter = Interpreter::new();
+    let mut eval = |text: &str| interpreter.eval_str(text).map(|value| value.to_string());
+    // string->number shares the grammar of number literals
+    assert_eq!(eval(r##"(list (string->number "100") (string->number "1e2") (string->number "#x1F") (string->number "-1/2") (string->number "1@0") (string->number "-inf.0-i"))"##)?,
+        "(100 100.0 31 -1/2 1 -inf.0-i)");
+    assert_eq!(eval(r##"(list (string->number "ff" 16) (string->number "101" 2) (string->number "#d10" 16) (string->number "#e1.5") (string->number "#i1/4") (exact? #d1.5))"##)?,
+        "(255 5 10 3/2 0.25 #f)");
+    assert_eq!(eval(r##"(list (string->number "") (string->number "abc") (string->number "1 2") (string->number "1.5" 16) (string->number "1/0") (string->number "+") (string->number "#x#x1"))"##)?,
+        "(#f #f #f #f #f #f #f)");
+    // number->string
+    assert_eq!(eval("(list (number->string 0.1) (number->string 1e21) (number->string 1e-7) (number->string 123.0) (number->string (/ 1.0 3)))")?,
+        r#"("0.1" "1e21" "1e-7" "123.0" "0.3333333333333333")"#);
+    assert_eq!(eval("(list (number->string 255 16) (number->string -10 2) (number->string 7/8 8) (number->string 3-i 2) (number->string 1.5 10))")?,
+        r#"("ff" "-1010" "7/10" "11-i" "1.5")"#);
+    assert_eq!(eval("(let ((x (/ 2.0 3))) (= x (string->number (number->string x))))")?, "#t");
+
+    assert_eq!(interpreter.eval_str("(number->string 1.5 2)"), Err(RuntimeError::WrongType(0, "exact number").with_span(span(0, 22))));
+    assert_eq!(interpreter.eval_str(r#"(string->number "1" 7)"#), Err(RuntimeError::WrongType(1, "radix").with_span(span(0, 22))));
+    Ok(())
+}
+
+#[test]
+fn load() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("risp-load-{}.scm", std::process::id()));
+    std::fs::write(&path, "(define loaded 'yes)\n\n  (car loaded)\n").unwrap();
+
+    let mut interpreter = Interpreter::new();
+    let error = match interpreter.load(&path).unwrap_err() {
+        LoadError::Runtime(error) => error,
+        error => panic!("{:?}", error),
+    };
+    std::fs::remove_file(&path).unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(error.data, RuntimeError::WrongType(0, "pair"));
+    assert_eq!(interpreter.eval_str("loaded")?, Value::Symbol("yes".to_string()));
+
+    let position = interpreter.sources().resolve(error.location()).unwrap();
+    assert_eq!(position.to_string(), format!("{}:3:3", path));
+    assert_eq!(interpreter.sources().line(error.location()), Some("  (car loaded)"));
+
+    // nothing was read, so there is no location to report
+    match interpreter.load(path).unwrap_err() {
+        LoadError::Io(missing, error) => {
+            assert_eq!(missing, path);
+            assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        }
+        error => panic!("{:?}", error),
+    }
+    Ok(())
+}
@@ -1,12 +1,24 @@
 use std::iter::Iterator;
 use peekmore::{PeekMore, PeekMoreIterator};
+use num_bigint::BigInt;
+use num_rational::BigRational;
 
-use super::{Token, Primitive, Complex, Real, Located, Location, LexerError, ToLocated};
+use super::{Token, Primitive, Complex, Real, Located, Location, Span, FileId, LexerError, ToLocated, CHARACTER_NAMES};
 
 pub struct Lexer<CharIter: Iterator<Item = char>> {
     char_stream: PeekMoreIterator<CharIter>,
     advance_location: Location,
     peek_location: Location,
+    // set by `#!fold-case` and cleared by `#!no-fold-case`
+    fold_case: bool,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct LexerOptions {
+    // 2.1 read as if the input started with `#!fold-case`
+    pub fold_case: bool,
+    // the file of the locations of the tokens
+    pub file: FileId,
 }
 
 type Result<T> = std::result::Result<T, Located<LexerError>>;
@@ -16,21 +28,37 @@ impl<CharIter: Iterator<Item = char>> Iterator for Lexer<CharIter> {
     fn next(&mut self) -> Option<Self::Item> {
         match self.get_next_token() {
             Ok(None) => None,
-            Ok(Some(Located{data, location})) => Some(Ok(data).with_location(location)),
-            Err(Located{data, location}) => Some(Err(data).with_location(location)),
+            Ok(Some(Located{data, span})) => Some(Ok(data).with_span(span)),
+            Err(Located{data, span}) => Some(Err(data).with_span(span)),
         }
     }
 }
 
 impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
     pub fn new(char_stream: CharIter) -> Self {
+        Self::with_options(char_stream, LexerOptions::default())
+    }
+
+    pub fn with_options(char_stream: CharIter, options: LexerOptions) -> Self {
         Self {
             char_stream: char_stream.peekmore(),
-            advance_location: Location{row: 0, col: 0},
-            peek_location: Location{row: 0, col: 0},
+            advance_location: Location{file: options.file, ..Location::default()},
+            peek_location: Location{file: options.file, ..Location::default()},
+            fold_case: options.fold_case,
         }
     }
 
+    // The location just past the last character consumed.
+    pub fn location(&self) -> Location {
+        self.advance_location
+    }
+
+    // Whether identifiers and characters are folded, as set by the options
+    // and the `#!fold-case` directives read so far.
+    pub fn is_folding_case(&self) -> bool {
+        self.fold_case
+    }
+
     fn get_next_token(&mut self) -> Result<Option<Located<Token>>> {
         while let (Some(ch), location) = self.peek_with_location() {
             return Ok(Some(match ch {
@@ -39,20 +67,20 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
                 '('  => {self.advance(); Token::LeftParen},
                 ')'  => {self.advance(); Token::RightParen},
                 '#'  => match self.peek() {
-                    Some('|')  => {self.reset(); self.skip_block_comment(); continue},
+                    Some('|')  => {self.reset(); self.skip_block_comment()?; continue},
+                    Some(';')  => {self.advance_n(2); Token::DatumComment},
+                    Some('!')  => {self.reset(); self.skip_directive()?; continue},
+                    Some('0'..='9') => {self.reset(); self.get_label()?},
                     Some('(')  => {self.advance_n(2); Token::VecConsIntro},
-                    Some('t')  => {self.advance_n(2); Token::Primitive(Primitive::Boolean(true))},
-                    Some('f')  => {self.advance_n(2); Token::Primitive(Primitive::Boolean(false))},
-                    Some('\\') => match self.peek() {
-                        Some(ch) => {self.advance_n(3); Token::Primitive(Primitive::Character(ch))},
-                        None => return located_error!(LexerError::UnexpectedEnd, location),
-                    },
+                    Some('t') | Some('f') => {self.reset(); self.get_boolean()?},
+                    Some('\\') => {self.reset(); self.get_character()?},
                     Some('u') => match (self.peek(), self.peek()) {
                         (Some('8'), Some('(')) => {self.advance_n(4); Token::ByteVecConsIntro},
                         _ => return located_error!(LexerError::UnrecognizedToken, location),
                     }
-                    Some('e') | Some('i') | Some('b') | Some('o') | Some('d') | Some('x') => {
-                        let (radix, exactness) = self.get_complex_prefix()?;
+                    Some('e') | Some('i') | Some('b') | Some('o') | Some('d') | Some('x')
+                    | Some('E') | Some('I') | Some('B') | Some('O') | Some('D') | Some('X') => {
+                        let (radix, exactness) = self.get_complex_prefix(Radix::Decimal)?;
                         Token::Primitive(Primitive::Complex(self.get_complex(radix, exactness)?))
                     },
                     Some(_) => return located_error!(LexerError::UnrecognizedToken, location),
@@ -65,22 +93,22 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
                     _         => {self.advance(); self.reset(); Token::Unquote},
                 },
                 '.' => match self.peek() {
-                    Some(ch) if is_delimiter(ch) => {self.advance(); Token::Period},
-                    None                              => {self.advance(); Token::Period},
-                    Some(ch) if '0' <= ch && ch <= '9' => {
+                    Some(ch) if is_delimiter(ch) => {self.advance(); self.reset(); Token::Period},
+                    None                              => {self.advance(); self.reset(); Token::Period},
+                    Some('0'..='9') => {
                         self.reset(); 
-                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                     }
                     Some(_) => {self.reset(); self.get_percular_identifier()?},
                 },
                 '+' | '-' => match self.peek() {
                     Some('0'..='9') => {
                         self.reset(); 
-                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                     }, 
                     Some('.') => {
                         self.reset(); 
-                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                     },
                     Some('i') if ({
                         let ch = self.peek();
@@ -88,27 +116,27 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
                         ch.is_some() && is_delimiter(ch.unwrap())
                     }) => {
                         self.reset();
-                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                     },
                     Some('i') if self.has_specific_string("nf.0") => {
                         self.reset();
-                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                     },
                     Some('n') if self.has_specific_string("an.0") => {
                         self.reset();
-                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                        Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                     },
                     Some(_) | None => {self.reset(); self.get_percular_identifier()?},
                 },
                 '0'..='9' => {
                     self.reset(); 
-                    Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, true)?))
+                    Token::Primitive(Primitive::Complex(self.get_complex(Radix::Decimal, Exactness::Default)?))
                 },
                 '"' => {self.reset(); self.get_string()?},
                 '|' => {self.reset(); self.get_quoted_identifier()?},
                 ch if is_identifier_initial(ch) => {self.reset(); self.get_normal_identifier()?},
                 _ => return located_error!(LexerError::UnexpectedBegin, location),
-            }.with_location(location)))
+            }.with_span(Span{start: location, end: self.advance_location})))
         }
 
         Ok(None)
@@ -136,15 +164,15 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
     }
 
     // <num> -> <refix> <complex> 
-    fn get_complex_prefix(&mut self) -> Result<(Radix, bool)> {
+    fn get_complex_prefix(&mut self, default_radix: Radix) -> Result<(Radix, Exactness)> {
         let ch = self.advance();
         debug_assert_eq!(Some('#'), ch);
 
         let mut exactness = None;
         let mut radix = None;
-        match self.advance() {
-            Some('e') => exactness = Some(true),
-            Some('i') => exactness = Some(false),
+        match self.advance().map(|ch| ch.to_ascii_lowercase()) {
+            Some('e') => exactness = Some(Exactness::Exact),
+            Some('i') => exactness = Some(Exactness::Inexact),
             Some('b') => radix = Some(2),
             Some('o') => radix = Some(8),
             Some('d') => radix = Some(10),
@@ -155,7 +183,7 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
 
         if self.peek() == Some('#') {
             self.advance();
-            let ch = self.advance();
+            let ch = self.advance().map(|ch| ch.to_ascii_lowercase());
             match ch {
                 Some('e') |
                 Some('i') => if exactness.is_some() {
@@ -171,8 +199,8 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
                 None => return located_error!(LexerError::UnexpectedEnd, self.advance_location),
             }
             match ch {
-                Some('e') => exactness = Some(true),
-                Some('i') => exactness = Some(false),
+                Some('e') => exactness = Some(Exactness::Exact),
+                Some('i') => exactness = Some(Exactness::Inexact),
                 Some('b') => radix = Some(2),
                 Some('o') => radix = Some(8),
                 Some('d') => radix = Some(10),
@@ -184,17 +212,38 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
             self.reset();
         }
 
-        if exactness.is_none() {
-            exactness = Some(true);
-        }
-        if radix.is_none() {
-            radix = Some(10);
-        }
+        let radix = radix.map_or(default_radix, |radix| Radix::from(radix).unwrap());
+        Ok((radix, exactness.unwrap_or(Exactness::Default)))
+    }
 
-        Ok((Radix::from(radix.unwrap()).unwrap(), exactness.unwrap()))
+    // A number with an optional prefix, as `string->number` reads it.
+    fn get_number(&mut self, radix: Radix) -> Result<Complex> {
+        let (radix, exactness) = match self.peek() {
+            Some('#') => {
+                self.reset();
+                self.get_complex_prefix(radix)?
+            },
+            _ => {
+                self.reset();
+                (radix, Exactness::Default)
+            },
+        };
+        self.get_complex(radix, exactness)
     }
 
-    fn get_complex(&mut self, radix: Radix, exactness: bool) -> Result<Complex> {
+    fn get_complex(&mut self, radix: Radix, exactness: Exactness) -> Result<Complex> {
+        let location = self.advance_location;
+        let complex = self.get_complex_parts(radix, exactness)?;
+        self.expect_delimiter()?;
+        match exactness.apply(complex) {
+            // an exact zero imaginary part makes the number real
+            Some(Complex::Complex(real, imaginary)) => Ok(Complex::new(real, imaginary)),
+            Some(complex) => Ok(complex),
+            None => located_error!(LexerError::InvalidNumber, location),
+        }
+    }
+
+    fn get_complex_parts(&mut self, radix: Radix, exactness: Exactness) -> Result<Complex> {
         // case 11 12
         match (self.peek(), self.peek(), self.peek()) {
             (Some('+'), Some('i'), last) |
@@ -227,51 +276,41 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
             Some('@') => {
                 self.advance();
                 let r2 = self.get_real(radix, exactness)?;
-                Complex::Complex(r1, r2)
+                Complex::polar(&r1, &r2)
             },
             // case 3 5 7
             Some('+') => if Some('i') == self.peek() {
                 self.reset();
                 if let Ok(r2) = self.get_infnan() {
+                    self.expect_i()?;
                     Complex::Complex(r1, r2)
                 } else {
-                    self.advance_n(2);
+                    // `get_infnan` has already consumed the sign
+                    self.advance();
                     Complex::Complex(r1, Real::Integer(1))
                 }
             } else {
                 self.reset();
                 let r2 = self.get_real(radix, exactness)?;
-                let (ch, location) = self.peek_with_location();
-                match ch {
-                    Some('i') => {
-                        self.advance();
-                        Complex::Complex(r1, r2)
-                    },
-                    Some(_) => return located_error!(LexerError::UnrecognizedToken, location),
-                    None => return located_error!(LexerError::UnexpectedEnd, location)
-                }
+                self.expect_i()?;
+                Complex::Complex(r1, r2)
             },
             // case 4 6 7
             Some('-') => if Some('i') == self.peek() {
                 self.reset();
                 if let Ok(r2) = self.get_infnan() {
+                    self.expect_i()?;
                     Complex::Complex(r1, r2)
                 } else {
-                    self.advance_n(2);
+                    // `get_infnan` has already consumed the sign
+                    self.advance();
                     Complex::Complex(r1, Real::Integer(-1))
                 }
             } else {
                 self.reset();
                 let r2 = self.get_real(radix, exactness)?;
-                let (ch, location) = self.peek_with_location();
-                match ch {
-                    Some('i') => {
-                        self.advance();
-                        Complex::Complex(r1, r2)
-                    },
-                    Some(_) => return located_error!(LexerError::UnrecognizedToken, location),
-                    None => return located_error!(LexerError::UnexpectedEnd, location)
-                }
+                self.expect_i()?;
+                Complex::Complex(r1, r2)
             },
             // case 8 9 10
             Some('i') => {
@@ -281,11 +320,11 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
                 }
                 Complex::Imaginary(r1)
             },
-            Some(_) => return located_error!(LexerError::UnrecognizedToken, location),
+            Some(_) => return located_error!(LexerError::ExpectedDelimiter, location),
         })
     }
 
-    fn get_real(&mut self, radix: Radix, exactness: bool) -> Result<Real> {
+    fn get_real(&mut self, radix: Radix, exactness: Exactness) -> Result<Real> {
         match (self.peek(), self.peek()) {
             (Some('+'), Some('i')) |
             (Some('-'), Some('i')) |
@@ -311,63 +350,71 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
         }
     }
 
-    fn get_unreal(&mut self, radix: Radix, _exactness: bool) -> Result<Real> {
+    fn get_unreal(&mut self, radix: Radix, exactness: Exactness) -> Result<Real> {
         let (ch, location) = self.peek_with_location();
         match ch {
             Some(ch) if radix.contains(ch) => {
                 self.reset();
                 let n1 = self.get_digit(radix)?;
-                assert!(!n1.is_empty());
-                match self.peek() {
+                let (ch, next) = self.peek_with_location();
+                match ch {
                     Some(ch) 
                     if is_delimiter(ch) 
                     || ch == '@' || ch == 'i'
                     || ch == '+' || ch == '-' // TODO
                     => {
                         self.reset();
-                        let int = n1.parse().unwrap();
-                        Ok(Real::Integer(int))
+                        Ok(Real::integer(parse_integer(&n1, radix)))
                     },
                     None => {
                         self.reset();
-                        let int = n1.parse().unwrap();
-                        Ok(Real::Integer(int))
+                        Ok(Real::integer(parse_integer(&n1, radix)))
                     },
                     Some('/') => {
                         self.advance();
+                        let denominator_location = self.advance_location;
                         let n2 = self.get_digit(radix)?;
-                        assert!(!n2.is_empty());
-                        let i1 = n1.parse().unwrap();
-                        let i2 = n2.parse().unwrap();
-                        Ok(Real::Ration(i1, i2))
+                        if n2.is_empty() {
+                            return located_error!(LexerError::InvalidNumber, self.advance_location);
+                        }
+                        match Real::fraction(parse_integer(&n1, radix), parse_integer(&n2, radix)) {
+                            Some(real) => Ok(real),
+                            None => located_error!(LexerError::InvalidNumber, denominator_location),
+                        }
                     }
+                    // 7.1.1 decimal points and exponents are only allowed in radix 10
+                    Some('.') | Some('e') if radix.base() != 10 => {
+                        located_error!(LexerError::InvalidNumber, next)
+                    },
                     Some('.') => {
                         self.advance();
                         let n2 = self.get_digit(radix)?;
                         let suffix = self.get_suffix()?;
-                        let f = (n1 + "." + &n2 + &suffix).parse().unwrap();
-                        Ok(Real::Float(f))
+                        decimal(&n1, &n2, &suffix, exactness, location)
                     },
                     Some('e') => {
                         self.reset();
                         let suffix = self.get_suffix()?;
-                        let f = (n1 + &suffix).parse().unwrap();
-                        Ok(Real::Float(f))
+                        decimal(&n1, "", &suffix, exactness, location)
                     },
-                    Some(_) => return located_error!(LexerError::UnrecognizedToken, location),
+                    // a digit beyond the radix
+                    Some(ch) if ch.is_ascii_digit() => located_error!(LexerError::InvalidNumber, next),
+                    Some(_) => located_error!(LexerError::ExpectedDelimiter, next),
                 }
             },
+            Some('.') if radix.base() != 10 => located_error!(LexerError::InvalidNumber, location),
             Some('.') => {
                 self.advance();
                 let n2 = self.get_digit(radix)?;
-                assert!(!n2.is_empty());
+                if n2.is_empty() {
+                    return located_error!(LexerError::InvalidNumber, self.advance_location);
+                }
                 let suffix = self.get_suffix()?;
-                let unreal = '.'.to_string() + &n2 + &suffix;
-                let unreal = unreal.parse().unwrap();
-                Ok(Real::Float(unreal))
+                decimal("", &n2, &suffix, exactness, location)
             },
-            Some(_) => return located_error!(LexerError::UnrecognizedToken, location),
-            None => return located_error!(LexerError::UnexpectedEnd, location),
+            Some(ch) if ch.is_ascii_alphanumeric() => located_error!(LexerError::InvalidNumber, location),
+            Some(_) => located_error!(LexerError::UnrecognizedToken, location),
+            None => located_error!(LexerError::UnexpectedEnd, location),
         }
     }
 
@@ -387,7 +434,9 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
                 self.reset();
             }
             let digit = self.get_digit(Radix::Decimal)?;
-            assert!(!digit.is_empty());
+            if digit.is_empty() {
+                return located_error!(LexerError::InvalidNumber, self.advance_location);
+            }
             Ok(res + &digit)
         }
     }
@@ -406,6 +455,108 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
         Ok(res)
     }
 
+    // 7.1.1 numbers, booleans, characters and label references must be
+    // followed by a delimiter or the end of the input.
+    fn expect_delimiter(&mut self) -> Result<()> {
+        self.reset();
+        let (ch, location) = self.peek_with_location();
+        self.reset();
+        match ch {
+            Some(ch) if !is_delimiter(ch) => located_error!(LexerError::ExpectedDelimiter, location),
+            _ => Ok(()),
+        }
+    }
+
+    // The `i` closing the imaginary part of a rectangular number.
+    fn expect_i(&mut self) -> Result<()> {
+        let (ch, location) = self.peek_with_location();
+        match ch {
+            Some('i') => {
+                self.advance();
+                Ok(())
+            },
+            Some(_) => located_error!(LexerError::UnrecognizedToken, location),
+            None => located_error!(LexerError::UnexpectedEnd, location)
+        }
+    }
+
+    // <boolean> -> #t | #f | #true | #false
+    fn get_boolean(&mut self) -> Result<Token> {
+        let location = self.advance_location;
+        self.advance();
+        // the longest boolean the text starts with, and where it ends
+        let mut name = String::new();
+        let mut boolean = None;
+        loop {
+            match name.as_str() {
+                "t" | "true" => boolean = Some((true, self.advance_location)),
+                "f" | "false" => boolean = Some((false, self.advance_location)),
+                _ => (),
+            }
+            match self.peek() {
+                Some(ch) if !is_delimiter(ch) => {
+                    name.push_str(&self.folded(ch));
+                    self.advance();
+                },
+                _ => break,
+            }
+        }
+        self.reset();
+        match boolean {
+            Some((b, end)) if end == self.advance_location => Ok(Token::Primitive(Primitive::Boolean(b))),
+            Some((_, end)) => located_error!(LexerError::ExpectedDelimiter, end),
+            None => located_error!(LexerError::UnrecognizedToken, location),
+        }
+    }
+
+    // <label> -> #<uinteger 10>= | #<uinteger 10>#
+    fn get_label(&mut self) -> Result<Token> {
+        let location = self.advance_location;
+        self.advance();
+        let n = self.get_digit(Radix::Decimal)?.parse::<usize>();
+        match (self.advance(), n) {
+            (Some('='), Ok(n)) => Ok(Token::Label(n)),
+            (Some('#'), Ok(n)) => {
+                self.expect_delimiter()?;
+                Ok(Token::LabelReference(n))
+            },
+            (None, _) => located_error!(LexerError::UnexpectedEnd, location),
+            _ => located_error!(LexerError::UnrecognizedToken, location),
+        }
+    }
+
+    // <character> -> #\\<any character> | #\\<character name> | #\\x<hex scalar value>
+    fn get_character(&mut self) -> Result<Token> {
+        let location = self.advance_location;
+        self.advance_n(2);
+        let first = match self.advance() {
+            Some(ch) => ch,
+            None => return located_error!(LexerError::UnexpectedEnd, location),
+        };
+        // the first character is taken as is, even a delimiter; a name or
+        // scalar value runs up to the next delimiter, and the longest
+        // character the text starts with is kept with where it ends
+        let mut name = self.folded(first);
+        let mut character = (first, self.advance_location);
+        while let Some(ch) = self.peek() {
+            if is_delimiter(ch) {
+                break;
+            }
+            name.push_str(&self.folded(ch));
+            self.advance();
+            if let Some(ch) = named_character(&name) {
+                character = (ch, self.advance_location);
+            }
+        }
+        self.reset();
+        match character {
+            (ch, end) if end == self.advance_location => Ok(Token::Primitive(Primitive::Character(ch))),
+            // a scalar value that names no character
+            _ if is_hex_scalar(&name) => located_error!(LexerError::InvalidCharacter, location),
+            (_, end) => located_error!(LexerError::ExpectedDelimiter, end),
+        }
+    }
+
     fn get_infnan(&mut self) -> Result<Real> {
         let sign = self.advance();
         let location = self.advance_location;
@@ -445,15 +596,17 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
         debug_assert_eq!(ch, Some('|'));
 
         let mut identifier_str = String::new();
-        while let Some(ch) = self.advance() {
-            match ch {
-                '|' => return Ok(Token::Identifier(
+        loop {
+            let location = self.advance_location;
+            match self.advance() {
+                Some('|') => return Ok(Token::Identifier(
                     if identifier_str.is_empty() {"||".to_string()} else {identifier_str}
                 )),
-                ch => identifier_str.push(ch),
+                Some('\\') => identifier_str.push(self.get_escaped_character(location)?),
+                Some(ch) => identifier_str.push(ch),
+                None => return located_error!(LexerError::UnrecognizedToken, self.advance_location),
             }
         }
-        located_error!(LexerError::UnrecognizedToken, self.advance_location)
     }
 
     fn get_normal_identifier(&mut self) -> Result<Token> {
@@ -464,7 +617,7 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
 
         let mut identifier_string = ch.to_string();
         self.get_subsequent(&mut identifier_string)?;
-        Ok(Token::Identifier(identifier_string))
+        Ok(self.identifier(identifier_string))
     }
 
     fn get_subsequent(&mut self, identifier_string: &mut String) -> Result<()> {
@@ -486,30 +639,79 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
         debug_assert_eq!(ch, Some('\"'));
 
         let mut string_literal = String::new();
-        while let Some(ch) = self.advance() {
-            match ch {
-                '"' => return Ok(Token::Primitive(Primitive::String(string_literal))),
-                '\\' => match self.advance() {
-                    Some(ec) => match ec {
-                        'a' => string_literal.push('\u{007}'),
-                        'b' => string_literal.push('\u{008}'),
-                        't' => string_literal.push('\u{009}'),
-                        'n' => string_literal.push('\n'),
-                        'r' => string_literal.push('\r'),
-                        '"' => string_literal.push('"'),
-                        '\\' => string_literal.push('\\'),
-                        '|' => string_literal.push('|'),
-                        'x' => (), // TODO: 'x' for hex value
-                        ' ' => (), // TODO: space for nothing
-                        _ => return located_error!(LexerError::UnrecognizedToken, self.advance_location),
-                    }
-                    None => return located_error!(LexerError::UnexpectedEnd, self.advance_location),
+        loop {
+            let location = self.advance_location;
+            match self.advance() {
+                Some('"') => return Ok(Token::Primitive(Primitive::String(string_literal))),
+                Some('\\') => match self.peek() {
+                    Some(' ') | Some('\t') | Some('\n') | Some('\r') => {
+                        self.reset();
+                        self.skip_line_continuation(location)?;
+                    },
+                    _ => {
+                        self.reset();
+                        string_literal.push(self.get_escaped_character(location)?);
+                    },
                 },
-                _ => string_literal.push(ch),
+                Some(ch) => string_literal.push(ch),
+                None => return located_error!(LexerError::UnexpectedEnd, self.advance_location),
             }
         }
+    }
+
+    // The character written by an escape in a string or `|...|` identifier;
+    // the backslash at `location` has been consumed.
+    fn get_escaped_character(&mut self, location: Location) -> Result<char> {
+        match self.advance() {
+            Some('a') => Ok('\u{007}'),
+            Some('b') => Ok('\u{008}'),
+            Some('t') => Ok('\u{009}'),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some(ch @ '"') | Some(ch @ '\\') | Some(ch @ '|') => Ok(ch),
+            // <inline hex escape> -> \x<hex scalar value>;
+            Some('x') | Some('X') => {
+                let mut hex = String::new();
+                loop {
+                    match self.advance() {
+                        Some(';') => break,
+                        Some(ch) if ch.is_ascii_hexdigit() => hex.push(ch),
+                        Some(_) => return located_error!(LexerError::InvalidEscape, location),
+                        None => return located_error!(LexerError::UnexpectedEnd, self.advance_location),
+                    }
+                }
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(ch) => Ok(ch),
+                    None => located_error!(LexerError::InvalidEscape, location),
+                }
+            },
+            Some(_) => located_error!(LexerError::InvalidEscape, location),
+            None => located_error!(LexerError::UnexpectedEnd, self.advance_location),
+        }
+    }
 
-        return located_error!(LexerError::UnexpectedEnd, self.advance_location)
+    // \<intraline whitespace>*<line ending><intraline whitespace>*, which
+    // stands for nothing; the backslash at `location` has been consumed.
+    fn skip_line_continuation(&mut self, location: Location) -> Result<()> {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.advance();
+        }
+        self.reset();
+        match self.advance() {
+            Some('\n') => (),
+            Some('\r') => if self.peek() == Some('\n') {
+                self.advance();
+            } else {
+                self.reset();
+            },
+            Some(_) => return located_error!(LexerError::InvalidEscape, location),
+            None => return located_error!(LexerError::UnexpectedEnd, self.advance_location),
+        }
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.advance();
+        }
+        self.reset();
+        Ok(())
     }
 
     fn get_percular_identifier(&mut self) -> Result<Token> {
@@ -553,7 +755,45 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
             },
             _ => panic!("unexpected"),
         }
-        Ok(Token::Identifier(identifier_string))
+        Ok(self.identifier(identifier_string))
+    }
+
+    // Identifiers outside `|...|` are case folded under `#!fold-case`.
+    fn identifier(&self, name: String) -> Token {
+        Token::Identifier(if self.fold_case {fold_case(&name)} else {name})
+    }
+
+    // 2.1 `#!fold-case` and `#!no-fold-case`; on the first line, `#!` starts
+    // an interpreter line as in `#!/usr/bin/env risp`, skipped like a comment.
+    fn skip_directive(&mut self) -> Result<()> {
+        let location = self.advance_location;
+        self.advance_n(2);
+        let mut name = String::new();
+        let mut delimiter = None;
+        while let Some(ch) = self.peek() {
+            if is_delimiter(ch) {
+                delimiter = Some(ch);
+                break;
+            }
+            name.push(ch);
+            self.advance();
+        }
+        self.reset();
+        // a script's `#!/path` or `#! path` line, but no other unknown name
+        let shebang = location.offset == 0 && (name.starts_with('/') || (name.is_empty() && delimiter == Some(' ')));
+        match name.as_str() {
+            "fold-case" => self.fold_case = true,
+            "no-fold-case" => self.fold_case = false,
+            _ if shebang => {
+                while let Some(ch) = self.advance() {
+                    if ch == '\n' {
+                        break;
+                    }
+                }
+            },
+            _ => return located_error!(LexerError::UnrecognizedToken, location),
+        }
+        Ok(())
     }
 
     // fn get_complex_suffix(&mut self, number_literal: &mut String) {
@@ -568,12 +808,20 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
     //     self.get_digital(number_literal)
     // }
 
+    // `ch` as it reads under the current case folding.
+    fn folded(&self, ch: char) -> String {
+        match self.fold_case {
+            true => fold_case(&ch.to_string()),
+            false => ch.to_string(),
+        }
+    }
+
     fn peek_with_location(&mut self) -> (Option<char>, Location) {
         let location = self.peek_location;
-        let ch = self.char_stream.peek().map(|c| *c);
+        let ch = self.char_stream.peek().copied();
         self.char_stream.advance_cursor();
-        if ch.is_some() {
-            move_location(ch.unwrap(), &mut self.peek_location)
+        if let Some(ch) = ch {
+            move_location(ch, &mut self.peek_location)
         }
         (ch, location)
     }
@@ -584,13 +832,14 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
 
     fn reset(&mut self) {
         self.char_stream.reset_cursor();
+        self.peek_location = self.advance_location;
     }
 
     fn advance(&mut self) -> Option<char> {
         let ch = self.char_stream.next();
         
-        if ch.is_some() {
-            move_location(ch.unwrap(), &mut self.advance_location)
+        if let Some(ch) = ch {
+            move_location(ch, &mut self.advance_location)
         }
         self.peek_location = self.advance_location;
         ch
@@ -616,32 +865,63 @@ impl<CharIter: Iterator<Item = char>> Lexer<CharIter> {
         }
     }
 
-    fn skip_block_comment(&mut self) {
+    // Block comments nest; an unterminated one is an error at its `#|`.
+    fn skip_block_comment(&mut self) -> Result<()> {
+        let location = self.advance_location;
         let ch = self.advance();
         debug_assert_eq!(Some('#'), ch);
         let ch = self.advance();
         debug_assert_eq!(Some('|'), ch);
 
-        let mut flag = false;
+        let mut depth = 1;
         while let Some(ch) = self.advance() {
-            match ch {
-                '|' => flag = true,
-                '#' => if flag {break},
-                _ => flag = false,
+            match (ch, self.peek()) {
+                ('|', Some('#')) => {
+                    self.advance();
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                },
+                ('#', Some('|')) => {
+                    self.advance();
+                    depth += 1;
+                },
+                _ => self.reset(),
             }
         }
+        located_error!(LexerError::UnexpectedEnd, location)
     }
 }
 
-fn is_whitespace(ch: char) -> bool {
-    match ch {
-        ' ' | '\t' | '\n' | '\r' => true,
-        _ => false,
+// The character a name longer than one character stands for.
+fn named_character(name: &str) -> Option<char> {
+    CHARACTER_NAMES.iter().find(|(_, n)| *n == name).map(|(ch, _)| *ch).or_else(|| match name.strip_prefix('x') {
+        Some(hex) if is_hex_scalar(name) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+        _ => None,
+    })
+}
+
+fn is_hex_scalar(name: &str) -> bool {
+    match name.strip_prefix('x') {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|ch| ch.is_ascii_hexdigit()),
+        None => false,
     }
 }
 
+// 2.1 full Unicode case folding, under which `STRASSE` and `straße` agree.
+fn fold_case(text: &str) -> String {
+    caseless::default_case_fold_str(text)
+}
+
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\t' | '\n' | '\r')
+}
+
+// 2.1 leaves other Unicode characters in identifiers to the implementation;
+// letters are accepted, as in other Schemes.
 fn is_identifier_initial(c: char) -> bool {
-    match c {
+    (!c.is_ascii() && c.is_alphabetic()) || matches!(c,
         'a'..='z'
         | 'A'..='Z'
         | '!'
@@ -658,16 +938,12 @@ fn is_identifier_initial(c: char) -> bool {
         | '@'
         | '^'
         | '_'
-        | '~' => true,
-        _ => false,
-    }
+        | '~'
+    )
 }
 
 fn is_delimiter(c: char) -> bool {
-    match c {
-        ' ' | '\t' | '\n' | '\r' | '(' | ')' | '"' | ';' | '|' => true,
-        _ => false,
-    }
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '(' | ')' | '"' | ';' | '|')
 }
 
 fn is_sign_subsequent(c: char) -> bool {
@@ -678,6 +954,40 @@ fn is_sign_subsequent(c: char) -> bool {
     }
 }
 
+// The digits have been checked against `radix`.
+fn parse_integer(digits: &str, radix: Radix) -> BigInt {
+    BigInt::parse_bytes(digits.as_bytes(), radix.base()).unwrap()
+}
+
+// The largest power of ten an exact decimal may be scaled by; larger ones
+// would take too long to compute, if they fit in memory at all.
+const MAX_EXACT_SCALE: u64 = 100_000;
+
+// <decimal 10> with the digits before and after the point and the exponent
+// suffix; it is inexact unless prefixed with `#e`.
+fn decimal(integer: &str, fraction: &str, suffix: &str, exactness: Exactness, location: Location) -> Result<Real> {
+    let invalid = || LexerError::InvalidNumber.with_location(location);
+    if exactness != Exactness::Exact {
+        let text = format!("{}.{}{}", integer, fraction, suffix);
+        return text.parse().map(Real::float).map_err(|_| invalid());
+    }
+    let exponent = match suffix {
+        "" => 0,
+        suffix => suffix[1..].parse::<i32>().map_err(|_| invalid())?,
+    };
+    let digits = parse_integer(&format!("{}{}", integer, fraction), Radix::Decimal);
+    let scale = i64::from(exponent) - fraction.len() as i64;
+    if scale.unsigned_abs() > MAX_EXACT_SCALE {
+        return Err(invalid());
+    }
+    let power = num_traits::pow(BigInt::from(10), scale.unsigned_abs() as usize);
+    Ok(if scale >= 0 {
+        Real::integer(digits * power)
+    } else {
+        Real::fraction(digits, power).unwrap()
+    })
+}
+
 fn move_location(ch: char, location: &mut Location) {
     match ch {
         '\n' => {
@@ -686,14 +996,28 @@ fn move_location(ch: char, location: &mut Location) {
         },
         _ => location.col += 1,
     }
+    location.offset += ch.len_utf8();
+}
+
+// 6.2.7 string->number: reads `text` with the grammar of number literals, in
+// `radix` unless a prefix says otherwise. `None` when `text` is not exactly
+// one number or `radix` is not 2, 8, 10 or 16.
+pub fn parse_number(text: &str, radix: u32) -> Option<Complex> {
+    let mut lexer = Lexer::new(text.chars());
+    let number = lexer.get_number(Radix::from(radix as usize)?).ok()?;
+    match lexer.peek() {
+        None => Some(number),
+        Some(_) => None,
+    }
 }
 
+#[cfg(test)]
 fn tokenize(text: &str) -> Result<Vec<Token>> {
     let mut iter = text.chars().peekable();
     let c = Lexer::new(&mut iter);
     c.map(|a| {
-        let (res, location) = (a.data, a.location);
-        res.map_err(|e| e.with_location(location))
+        let (res, span) = (a.data, a.span);
+        res.map_err(|e| e.with_span(span))
     }).collect::<Result<Vec<_>>>()
 }
 
@@ -722,13 +1046,49 @@ impl Radix {
     pub fn contains(&self, ch: char) -> bool {
         match self {
             Radix::Binary  => '0' == ch || ch == '1',
-            Radix::Octal   => '0' <= ch && ch <= '7',
-            Radix::Decimal => '0' <= ch && ch <= '9',
-            Radix::Hexadecimal => '0' <= ch && ch <= '9' || 'a' <= ch && ch <= 'f',
+            Radix::Octal   => ('0'..='7').contains(&ch),
+            Radix::Decimal => ch.is_ascii_digit(),
+            Radix::Hexadecimal => ch.is_ascii_hexdigit(),
+        }
+    }
+
+    pub fn base(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hexadecimal => 16,
         }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Exactness {
+    // exact unless written with a decimal point or exponent
+    Default,
+    Exact,
+    Inexact,
+}
+
+impl Exactness {
+    // Returns `None` for an infinity or NaN made exact.
+    fn apply(self, complex: Complex) -> Option<Complex> {
+        let convert = |real: Real| match (self, real) {
+            (Exactness::Default, real) => Some(real),
+            (Exactness::Inexact, real) if real.is_exact() => Some(Real::float(real.to_f64())),
+            (Exactness::Inexact, real) => Some(real),
+            (Exactness::Exact, Real::Float(f)) => BigRational::from_float(f).map(Real::rational),
+            (Exactness::Exact, real) if real.is_exact() => Some(real),
+            (Exactness::Exact, _) => None,
+        };
+        Some(match complex {
+            Complex::Real(real) => Complex::Real(convert(real)?),
+            Complex::Complex(real, imaginary) => Complex::Complex(convert(real)?, convert(imaginary)?),
+            Complex::Imaginary(imaginary) => Complex::Imaginary(convert(imaginary)?),
+        })
+    }
+}
+
 // Identifiers
 // Identifiers have two uses within Scheme programs:
 // - Any identifier can be used as a variable or as a syntactic keyword
@@ -736,11 +1096,12 @@ impl Radix {
 #[test]
 fn identifier() -> Result<()> {
     let tests = vec![
-        // TODO
         // 2.1 inline hex escape
-        // (r"|H\x65;llo|",    Token::Identifier(String::from("Hello"))),
-        // (r"|\x3BB;|",       Token::Identifier(String::from("λ"))),
-        // (r"|\x9;\x9;|",     Token::Identifier(String::from("\t\t"))),
+        (r"|H\x65;llo|",    Token::Identifier(String::from("Hello"))),
+        (r"|\x3BB;|",       Token::Identifier(String::from("λ"))),
+        (r"|\x9;\x9;|",     Token::Identifier(String::from("\t\t"))),
+        (r"|a\|b|",         Token::Identifier(String::from("a|b"))),
+        (r"|a\\b\t|",       Token::Identifier(String::from("a\\b\t"))),
 
         // 2.1 examples of identifiers
         ("...",             Token::Identifier(String::from("..."))),
@@ -757,15 +1118,14 @@ fn identifier() -> Result<()> {
         ("|two; words|",    Token::Identifier(String::from("two; words"))),
         ("the-word-recursion-has-many-meanings", Token::Identifier(String::from("the-word-recursion-has-many-meanings"))),
 
-        // TODO
-        // 2.1 case insensitive inline hex escapes 
-        // (r"|\x3BB;|",       Token::Identifier(String::from("λ"))),
-        // (r"|\x3bb;|",       Token::Identifier(String::from("λ"))),
-
-        // TODO
-        // 2.1 explicit control over case folding.
-
+        // letters beyond ASCII
+        ("λ",               Token::Identifier(String::from("λ"))),
+        ("straße",          Token::Identifier(String::from("straße"))),
+        ("ΣΑΣ->x",          Token::Identifier(String::from("ΣΑΣ->x"))),
 
+        // 2.1 case insensitive inline hex escapes 
+        (r"|\x3BB;|",       Token::Identifier(String::from("λ"))),
+        (r"|\x3bb;|",       Token::Identifier(String::from("λ"))),
 
     ];
 
@@ -773,6 +1133,8 @@ fn identifier() -> Result<()> {
     let tokens = tests.iter().map(|p| p.1.clone()).collect::<Vec<_>>();
 
     assert_eq!(tokenize(&text)?, tokens);
+    // other symbols are still not identifier characters
+    assert_eq!(tokenize("a→b").unwrap_err().data, LexerError::UnrecognizedToken);
 
     Ok(())
 }
@@ -793,7 +1155,7 @@ fn whitespace() -> Result<()> {
         Token::RightParen,
     ]);
 
-    let tests = vec![
+    let tests = [
        ("\" \t\r\n\"", Token::Primitive(Primitive::String(String::from(" \t\r\n")))),
        ("| \t\r\n|",   Token::Identifier(String::from(" \t\r\n"))),
 
@@ -818,16 +1180,19 @@ fn comments() -> Result<()> {
         Token::RightParen,
     ]);
 
-    // TODO
-    // prefix a <datum> with #; and optional hwhitespacei.
-    // let text = "#; (- 2 1) (+ a b)";
-    // assert_eq!(tokenize(text)?, vec![
-    //     Token::LeftParen,
-    //     Token::Identifier(String::from("-")),
-    //     Token::Identifier(String::from("a")),
-    //     Token::Identifier(String::from("b")),
-    //     Token::RightParen,
-    // ]);
+    // prefix a <datum> with #; and optional whitespace; the processor skips
+    // the datum
+    let text = "#; (- 2 1) #;a";
+    assert_eq!(tokenize(text)?, vec![
+        Token::DatumComment,
+        Token::LeftParen,
+        Token::Identifier(String::from("-")),
+        Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(2)))),
+        Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(1)))),
+        Token::RightParen,
+        Token::DatumComment,
+        Token::Identifier(String::from("a")),
+    ]);
 
     // Block comments are indicated with properly nested #| and |# pairs.
     let text = "
@@ -843,6 +1208,12 @@ fn comments() -> Result<()> {
         Token::Identifier(String::from("fact")),
         Token::RightParen,
     ]);
+    let text = "#| outer #| inner |# still |# a #|# ||# b #||#";
+    assert_eq!(tokenize(text)?, vec![
+        Token::Identifier(String::from("a")),
+        Token::Identifier(String::from("b")),
+    ]);
+    assert_eq!(tokenize("a\n  #| #| |# b").unwrap_err(), LexerError::UnexpectedEnd.with_location(Location{row: 1, col: 2, offset: 4, ..Location::default()}));
 
     Ok(())
 }
@@ -871,26 +1242,109 @@ fn other_notations() -> Result<()> {
 }
 
 #[test]
-fn datum_labels() {
-    // TODO
+fn datum_labels() -> Result<()> {
+    assert_eq!(
+        tokenize("#0=(a . #0#) #12=#12# '#3=x")?,
+        vec![
+            Token::Label(0),
+            Token::LeftParen,
+            Token::Identifier(String::from("a")),
+            Token::Period,
+            Token::LabelReference(0),
+            Token::RightParen,
+            Token::Label(12),
+            Token::LabelReference(12),
+            Token::Quote,
+            Token::Label(3),
+            Token::Identifier(String::from("x")),
+        ]
+    );
+    assert_eq!(tokenize("a #1x").unwrap_err(), LexerError::UnrecognizedToken.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    assert_eq!(tokenize("#99999999999999999999999=a").unwrap_err(), LexerError::UnrecognizedToken.with_location(Location::default()));
+    assert_eq!(tokenize("#1").unwrap_err(), LexerError::UnexpectedEnd.with_location(Location::default()));
+    Ok(())
+}
+
+// 2.1 explicit control over case folding
+#[test]
+fn case_folding() -> Result<()> {
+    let identifier = |name: &str| Token::Identifier(String::from(name));
+    assert_eq!(
+        tokenize("Abc #!fold-case Abc +Ab |Abc| #\\SPACE #\\A ΣΑΣ #!no-fold-case Abc")?,
+        vec![
+            identifier("Abc"),
+            identifier("abc"),
+            identifier("+ab"),
+            identifier("Abc"),
+            Token::Primitive(Primitive::Character(' ')),
+            Token::Primitive(Primitive::Character('A')),
+            identifier("σασ"),
+            identifier("Abc"),
+        ]
+    );
+
+    let lexer = Lexer::with_options("Abc #\\Tab".chars(), LexerOptions{fold_case: true, ..LexerOptions::default()});
+    assert_eq!(
+        lexer.map(|token| token.data).collect::<std::result::Result<Vec<_>, _>>(),
+        Ok(vec![identifier("abc"), Token::Primitive(Primitive::Character('\t'))])
+    );
+    assert_eq!(
+        tokenize("#!fold-case STRASSE straße ΣΑΣ σας")?,
+        vec![identifier("strasse"), identifier("strasse"), identifier("σασ"), identifier("σασ")]
+    );
+    assert_eq!(tokenize("a #!fold").unwrap_err(), LexerError::UnrecognizedToken.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    Ok(())
+}
+
+// a script's interpreter line
+#[test]
+fn shebang() -> Result<()> {
+    assert_eq!(
+        tokenize("#!/usr/bin/env risp -q\n(a)")?,
+        vec![Token::LeftParen, Token::Identifier(String::from("a")), Token::RightParen]
+    );
+    assert_eq!(tokenize("#! /usr/bin/env risp\n(a)")?, vec![Token::LeftParen, Token::Identifier(String::from("a")), Token::RightParen]);
+    assert_eq!(tokenize("\n#!/usr/bin/env risp").unwrap_err(), LexerError::UnrecognizedToken.with_location(Location{row: 1, col: 0, offset: 1, ..Location::default()}));
+    // a misspelt directive is no interpreter line
+    assert_eq!(tokenize("#!fold-cas (+ 1 2)").unwrap_err(), LexerError::UnrecognizedToken.with_location(Location::default()));
+    Ok(())
 }
 
 // 6.6
 #[test]
 fn characters() -> Result<()> {
     assert_eq!(
-        tokenize("#\\a#\\ #\\\t")?,
+        tokenize("#\\a #\\  #\\\t")?,
         vec![
             Token::Primitive(Primitive::Character('a')),
             Token::Primitive(Primitive::Character(' ')),
             Token::Primitive(Primitive::Character('\t'))
         ]
     );
+    let character = |c| Token::Primitive(Primitive::Character(c));
+    assert_eq!(
+        tokenize("#\\space #\\newline #\\tab #\\alarm #\\null #\\delete #\\escape #\\backspace #\\return #\\x41 #\\x3bb #\\x #\\λ")?,
+        vec![
+            character(' '), character('\n'), character('\t'), character('\u{7}'), character('\0'), character('\u{7f}'),
+            character('\u{1b}'), character('\u{8}'), character('\r'), character('A'), character('λ'), character('x'), character('λ'),
+        ]
+    );
+    assert_eq!(
+        tokenize("(#\\a)(#\\()#\\;")?,
+        vec![
+            Token::LeftParen, character('a'), Token::RightParen,
+            Token::LeftParen, character('('), Token::RightParen, character(';'),
+        ]
+    );
+    assert_eq!(tokenize("#\\a #\\xd800").unwrap_err(), LexerError::InvalidCharacter.with_location(Location{row: 0, col: 4, offset: 4, ..Location::default()}));
+    assert_eq!(tokenize("#\\x110000").unwrap_err(), LexerError::InvalidCharacter.with_location(Location::default()));
+    assert_eq!(tokenize("#\\").unwrap_err(), LexerError::UnexpectedEnd.with_location(Location::default()));
     Ok(())
 }
 
 // num R
 #[test]
+#[allow(clippy::approx_constant)]
 fn number() -> Result<()> {
     assert_eq!(
         tokenize(
@@ -940,9 +1394,9 @@ fn number() -> Result<()> {
             Complex::Real(Real::Float(3000.0)),
             Complex::Real(Real::Float(3000.0)),
 
-            // case 2
-            Complex::Complex(Real::Integer(1), Real::Integer(-1)),
-            Complex::Complex(Real::Integer(1), Real::Float(0.0)),
+            // case 2, converted from polar form
+            Complex::Complex(Real::Float(1f64.cos()), Real::Float(-1f64.sin())),
+            Complex::Complex(Real::Float(1.0), Real::Float(0.0)),
 
             // case 3
             Complex::Complex(Real::Float(0.01), Real::Float(1.0)),
@@ -983,6 +1437,124 @@ fn number() -> Result<()> {
     Ok(())
 }
 
+// 7.1.1 <num R> with the radix prefixes
+#[test]
+fn radix() -> Result<()> {
+    let integer = |i| Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(i))));
+    assert_eq!(
+        tokenize("#xff #XFF #xDeadBeef #x-10 #b101 #B-11 #o777 #d99 #e#x10 #x#i10 #xe")?,
+        vec![
+            integer(255),
+            integer(255),
+            integer(0xdeadbeef),
+            integer(-16),
+            integer(5),
+            integer(-3),
+            integer(511),
+            integer(99),
+            integer(16),
+            Token::Primitive(Primitive::Complex(Complex::Real(Real::Float(16.0)))),
+            integer(14),
+        ]
+    );
+    assert_eq!(
+        tokenize("#x1/a #b-11/10 #x1+fi")?,
+        vec![
+            Token::Primitive(Primitive::Complex(Complex::Real(Real::Ration(1, 10)))),
+            Token::Primitive(Primitive::Complex(Complex::Real(Real::Ration(-3, 2)))),
+            Token::Primitive(Primitive::Complex(Complex::Complex(Real::Integer(1), Real::Integer(15)))),
+        ]
+    );
+
+    let error = |text: &str| tokenize(text).unwrap_err();
+    assert_eq!(error("#b102"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 4, offset: 4, ..Location::default()}));
+    assert_eq!(error("#o8"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    assert_eq!(error("#x1.5"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 3, offset: 3, ..Location::default()}));
+    assert_eq!(error("#b1e1"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 3, offset: 3, ..Location::default()}));
+    assert_eq!(error("(#x.1)"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 3, offset: 3, ..Location::default()}));
+    assert_eq!(error("1/"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    assert_eq!(error("1e+"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 3, offset: 3, ..Location::default()}));
+    assert_eq!(error("1/0"), LexerError::InvalidNumber.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    assert_eq!(error("#e1e99999999").data, LexerError::InvalidNumber);
+    assert_eq!(error("#e1e-99999999").data, LexerError::InvalidNumber);
+
+    Ok(())
+}
+
+// 6.2.1 exact numbers of any size, with rationals in lowest terms
+#[test]
+fn big_numbers() -> Result<()> {
+    let real = |r| Token::Primitive(Primitive::Complex(Complex::Real(r)));
+    let big = |s: &str| BigInt::parse_bytes(s.as_bytes(), 10).unwrap();
+    assert_eq!(
+        tokenize("12345678901234567890 -9223372036854775808 #x-10000000000000000 4/2 -6/4 0/5 1/18446744073709551616 18446744073709551616/3")?,
+        vec![
+            real(Real::BigInteger(Box::new(big("12345678901234567890")))),
+            real(Real::Integer(i64::MIN)),
+            real(Real::BigInteger(Box::new(big("-18446744073709551616")))),
+            real(Real::Integer(2)),
+            real(Real::Ration(-3, 2)),
+            real(Real::Integer(0)),
+            real(Real::BigRation(Box::new(num_rational::BigRational::new(big("1"), big("18446744073709551616"))))),
+            real(Real::BigRation(Box::new(num_rational::BigRational::new(big("18446744073709551616"), big("3"))))),
+        ]
+    );
+    Ok(())
+}
+
+// 6.2.5 #e and #i override the exactness of the literal
+#[test]
+fn exactness() -> Result<()> {
+    let real = |r| Token::Primitive(Primitive::Complex(Complex::Real(r)));
+    assert_eq!(
+        tokenize("#e1.5 #e1e3 #e-.25 #e1.2e-2 #e#x10 #x#e-1/2 #e0.1 #i1/3 #i#b101 #i-2 #i+inf.0 1.5 1/3 3")?,
+        vec![
+            real(Real::Ration(3, 2)),
+            real(Real::Integer(1000)),
+            real(Real::Ration(-1, 4)),
+            real(Real::Ration(3, 250)),
+            real(Real::Integer(16)),
+            real(Real::Ration(-1, 2)),
+            real(Real::Ration(1, 10)),
+            real(Real::Float(1.0 / 3.0)),
+            real(Real::Float(5.0)),
+            real(Real::Float(-2.0)),
+            real(Real::PosInf),
+            real(Real::Float(1.5)),
+            real(Real::Ration(1, 3)),
+            real(Real::Integer(3)),
+        ]
+    );
+    assert_eq!(
+        tokenize("#i1+2i #e1.5-0.5i #i+i")?,
+        vec![
+            Token::Primitive(Primitive::Complex(Complex::Complex(Real::Float(1.0), Real::Float(2.0)))),
+            Token::Primitive(Primitive::Complex(Complex::Complex(Real::Ration(3, 2), Real::Ration(-1, 2)))),
+            Token::Primitive(Primitive::Complex(Complex::Imaginary(Real::Float(1.0)))),
+        ]
+    );
+    // out of range decimals are the infinities themselves
+    assert_eq!(
+        tokenize(&format!("1e400 -1e400 #i1{}", "0".repeat(400)))?,
+        vec![real(Real::PosInf), real(Real::NegInf), real(Real::PosInf)]
+    );
+    assert_eq!(
+        tokenize("(1+i) (1-inf.0i) 1+0i #e1-0.0i")?,
+        vec![
+            Token::LeftParen,
+            Token::Primitive(Primitive::Complex(Complex::Complex(Real::Integer(1), Real::Integer(1)))),
+            Token::RightParen,
+            Token::LeftParen,
+            Token::Primitive(Primitive::Complex(Complex::Complex(Real::Integer(1), Real::NegInf))),
+            Token::RightParen,
+            Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(1)))),
+            Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(1)))),
+        ]
+    );
+    assert_eq!(tokenize("#e+nan.0").unwrap_err(), LexerError::InvalidNumber.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    Ok(())
+}
+
 #[test]
 fn string() -> Result<()> {
     assert_eq!(
@@ -993,6 +1565,20 @@ fn string() -> Result<()> {
             Token::Primitive(Primitive::String(String::from("\u{007}\u{008}\t\r\n\\|")))
         ]
     );
+    // 6.7 inline hex escapes and line continuations
+    assert_eq!(
+        tokenize("\"\\x3bb;\\x41;\\X9;x\" \"a\\   \n   b\" \"a\\\r\n\tb\\\nc\"")?,
+        vec![
+            Token::Primitive(Primitive::String(String::from("λA\tx"))),
+            Token::Primitive(Primitive::String(String::from("ab"))),
+            Token::Primitive(Primitive::String(String::from("abc"))),
+        ]
+    );
+    assert_eq!(tokenize("\"abc\\qdef\"").unwrap_err(), LexerError::InvalidEscape.with_location(Location{row: 0, col: 4, offset: 4, ..Location::default()}));
+    assert_eq!(tokenize("\"a\\x41\"").unwrap_err(), LexerError::InvalidEscape.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    assert_eq!(tokenize("\"\n \\xd800;\"").unwrap_err(), LexerError::InvalidEscape.with_location(Location{row: 1, col: 1, offset: 3, ..Location::default()}));
+    assert_eq!(tokenize("\"a\\  b\"").unwrap_err(), LexerError::InvalidEscape.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+    assert_eq!(tokenize("|a\\qb|").unwrap_err(), LexerError::InvalidEscape.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
     Ok(())
 }
 
@@ -1015,6 +1601,22 @@ fn delimiter() -> Result<()> {
             Token::RightParen,
         ]
     );
+
+    // 6.3 long-form booleans, which end at a delimiter like the short ones
+    let boolean = |b| Token::Primitive(Primitive::Boolean(b));
+    assert_eq!(
+        tokenize("#true #false(#t)#f;")?,
+        vec![boolean(true), boolean(false), Token::LeftParen, boolean(true), Token::RightParen, boolean(false)]
+    );
+    assert_eq!(tokenize("#tru").unwrap_err(), LexerError::ExpectedDelimiter.with_location(Location{row: 0, col: 2, offset: 2, ..Location::default()}));
+
+    // a complete literal that runs into the next token
+    for (text, col) in [
+        ("1/2x", 3), ("1.5x", 3), ("1+2ix", 4), ("1#", 1), ("1x", 1), ("+inf.0x", 6), ("#x1/2#", 5), ("#0=(a #0#b)", 9),
+        ("#tx", 2), ("#truex", 5), ("(#t#f)", 3), ("#\\ax", 3), ("#\\spacex", 7), ("#\\x41g", 5),
+    ] {
+        assert_eq!(tokenize(text).unwrap_err(), LexerError::ExpectedDelimiter.with_location(Location{row: 0, col, offset: col as usize, ..Location::default()}), "{}", text);
+    }
     Ok(())
 }
 
@@ -1058,3 +1660,18 @@ fn script() -> Result<()> {
     Ok(())
 }
 
+
+#[test]
+fn spans() -> Result<()> {
+    let spans = |text: &str| Lexer::new(text.chars()).map(|token| token.span.range()).collect::<Vec<_>>();
+    assert_eq!(spans("(a bc)"), vec![0..1, 1..2, 3..5, 5..6]);
+    assert_eq!(spans("\"λ\" #\\λ 1.5e2 ,@x"), vec![0..4, 5..9, 10..15, 16..18, 18..19]);
+
+    let tokens = Lexer::new("λ\n  #(1)".chars()).collect::<Vec<_>>();
+    assert_eq!(tokens[0].span, Span{start: Location::default(), end: Location{row: 0, col: 1, offset: 2, ..Location::default()}});
+    assert_eq!(tokens[1].span, Span{
+        start: Location{row: 1, col: 2, offset: 5, ..Location::default()},
+        end: Location{row: 1, col: 4, offset: 7, ..Location::default()},
+    });
+    Ok(())
+}
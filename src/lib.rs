@@ -3,7 +3,7 @@ mod model;
 pub use model::*;
 
 mod interpreter;
-pub use interpreter::Interpreter;
+pub use interpreter::{Interpreter, Continuation};
 
 mod processor;
 pub use processor::Processor;
@@ -14,5 +14,19 @@ pub use expression::Expression;
 mod environment;
 pub use environment::Environment;
 
+mod value;
+pub use value::{Value, Pair, Procedure, Control, Closure, NativeProcedure, Arity, Port, Record, RecordType, ErrorObject, ErrorKind};
+
+mod library;
+
+mod printer;
+pub use printer::{Printer, PrettyPrinter, Style};
+
+mod diagnostic;
+pub use diagnostic::{Diagnostic, Colour};
+
+mod convert;
+pub use convert::{FromScheme, IntoScheme, IntoNative, argument};
+
 mod lexer;
-pub use lexer::Lexer;
\ No newline at end of file
+pub use lexer::{Lexer, LexerOptions, parse_number};
@@ -0,0 +1,63 @@
+use std::rc::Rc;
+
+use super::{
+    define, symbol, string, error_object, Environment, Value, Procedure, Control, ErrorObject, ErrorKind, Arity,
+    RuntimeError, Result,
+};
+
+pub fn install(environment: &Environment) {
+    // 6.1 Equivalence predicates
+    define(environment, "eq?", Arity::Exactly(2), |args| Ok(Value::Boolean(args[0].is_eq(&args[1]))));
+    define(environment, "eqv?", Arity::Exactly(2), |args| Ok(Value::Boolean(args[0].eqv(&args[1]))));
+    define(environment, "equal?", Arity::Exactly(2), |args| Ok(Value::Boolean(args[0] == args[1])));
+
+    // 6.3 Booleans
+    define(environment, "not", Arity::Exactly(1), |args| Ok(Value::Boolean(!args[0].is_true())));
+    define(environment, "boolean?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Boolean(_)))));
+
+    // type predicates
+    define(environment, "symbol?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Symbol(_)))));
+    define(environment, "string?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::String(_)))));
+    define(environment, "char?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Character(_)))));
+    define(environment, "vector?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Vector(_)))));
+    define(environment, "bytevector?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::ByteVector(_)))));
+    define(environment, "procedure?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Procedure(_)))));
+
+    // 6.5 Symbols
+    define(environment, "symbol->string", Arity::Exactly(1), symbol_to_string);
+    define(environment, "string->symbol", Arity::Exactly(1), string_to_symbol);
+
+    // 6.10 Control features
+    define(environment, "values", Arity::AtLeast(0), |args| Ok(Value::values(args.to_vec())));
+    for control in [Control::Apply, Control::CallWithCurrentContinuation, Control::CallWithValues, Control::DynamicWind] {
+        environment.define(control.name(), Value::Procedure(Procedure::Control(control)));
+    }
+    environment.define("call/cc", Value::Procedure(Procedure::Control(Control::CallWithCurrentContinuation)));
+
+    // 6.11 Exceptions
+    for control in [Control::WithExceptionHandler, Control::RaiseContinuable] {
+        environment.define(control.name(), Value::Procedure(Procedure::Control(control)));
+    }
+    define(environment, "raise", Arity::Exactly(1), |args| error!(RuntimeError::Raised(args[0].clone())));
+    define(environment, "error", Arity::AtLeast(1), |args| {
+        let error = ErrorObject::new(&string(args, 0)?.borrow(), args[1..].to_vec());
+        error!(RuntimeError::Raised(Value::Error(Rc::new(error))))
+    });
+    define(environment, "error-object?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Error(_)))));
+    define(environment, "error-object-message", Arity::Exactly(1), |args| Ok(Value::string(&error_object(args, 0)?.message)));
+    define(environment, "error-object-irritants", Arity::Exactly(1), |args| Ok(Value::list(error_object(args, 0)?.irritants.clone())));
+    define(environment, "read-error?", Arity::Exactly(1), |args| {
+        Ok(Value::Boolean(matches!(&args[0], Value::Error(e) if matches!(e.kind, ErrorKind::Read(_)))))
+    });
+    define(environment, "file-error?", Arity::Exactly(1), |args| {
+        Ok(Value::Boolean(matches!(&args[0], Value::Error(e) if matches!(e.kind, ErrorKind::File))))
+    });
+}
+
+fn symbol_to_string(args: &[Value]) -> Result<Value> {
+    Ok(Value::string(symbol(args, 0)?))
+}
+
+fn string_to_symbol(args: &[Value]) -> Result<Value> {
+    Ok(Value::Symbol(string(args, 0)?.borrow().clone()))
+}
@@ -0,0 +1,100 @@
+use super::{define, pair, list, index, integer, Environment, Value, Arity, RuntimeError, Result};
+
+// 6.4 Pairs and lists
+pub fn install(environment: &Environment) {
+    define(environment, "pair?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Pair(_)))));
+    define(environment, "null?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Null))));
+    define(environment, "list?", Arity::Exactly(1), |args| Ok(Value::Boolean(args[0].list_to_vec().is_some())));
+
+    define(environment, "cons", Arity::Exactly(2), |args| Ok(Value::cons(args[0].clone(), args[1].clone())));
+    define(environment, "car", Arity::Exactly(1), |args| Ok(pair(args, 0)?.car()));
+    define(environment, "cdr", Arity::Exactly(1), |args| Ok(pair(args, 0)?.cdr()));
+    define(environment, "set-car!", Arity::Exactly(2), set_car);
+    define(environment, "set-cdr!", Arity::Exactly(2), set_cdr);
+    define(environment, "caar", Arity::Exactly(1), |args| pair(&[pair(args, 0)?.car()], 0).map(|p| p.car()));
+    define(environment, "cadr", Arity::Exactly(1), |args| pair(&[pair(args, 0)?.cdr()], 0).map(|p| p.car()));
+    define(environment, "cdar", Arity::Exactly(1), |args| pair(&[pair(args, 0)?.car()], 0).map(|p| p.cdr()));
+    define(environment, "cddr", Arity::Exactly(1), |args| pair(&[pair(args, 0)?.cdr()], 0).map(|p| p.cdr()));
+
+    define(environment, "list", Arity::AtLeast(0), |args| Ok(Value::list(args.to_vec())));
+    define(environment, "length", Arity::Exactly(1), |args| Ok(integer(list(args, 0)?.len())));
+    define(environment, "append", Arity::AtLeast(0), append);
+    define(environment, "reverse", Arity::Exactly(1), |args| Ok(Value::list(list(args, 0)?.into_iter().rev().collect::<Vec<_>>())));
+    define(environment, "list-tail", Arity::Exactly(2), list_tail);
+    define(environment, "list-ref", Arity::Exactly(2), |args| Ok(pair(&[list_tail(args)?], 0)?.car()));
+    define(environment, "list-copy", Arity::Exactly(1), list_copy);
+
+    define(environment, "memq", Arity::Exactly(2), |args| member(args, Value::is_eq));
+    define(environment, "memv", Arity::Exactly(2), |args| member(args, Value::eqv));
+    define(environment, "member", Arity::Exactly(2), |args| member(args, |a, b| a == b));
+    define(environment, "assq", Arity::Exactly(2), |args| assoc(args, Value::is_eq));
+    define(environment, "assv", Arity::Exactly(2), |args| assoc(args, Value::eqv));
+    define(environment, "assoc", Arity::Exactly(2), |args| assoc(args, |a, b| a == b));
+}
+
+fn set_car(args: &[Value]) -> Result<Value> {
+    pair(args, 0)?.set_car(args[1].clone());
+    Ok(Value::Unspecified)
+}
+
+fn set_cdr(args: &[Value]) -> Result<Value> {
+    pair(args, 0)?.set_cdr(args[1].clone());
+    Ok(Value::Unspecified)
+}
+
+fn append(args: &[Value]) -> Result<Value> {
+    let (last, lists) = match args.split_last() {
+        Some(split) => split,
+        None => return Ok(Value::Null),
+    };
+    let mut elements = vec![];
+    for i in 0..lists.len() {
+        elements.extend(list(args, i)?);
+    }
+    Ok(elements.into_iter().rev().fold(last.clone(), |cdr, car| Value::cons(car, cdr)))
+}
+
+fn list_tail(args: &[Value]) -> Result<Value> {
+    let k = index(args, 1)?;
+    let mut current = args[0].clone();
+    for _ in 0..k {
+        current = match current {
+            Value::Pair(pair) => pair.cdr(),
+            _ => return error!(RuntimeError::IndexOutOfRange(k)),
+        };
+    }
+    Ok(current)
+}
+
+// Copies the spine of a possibly improper list.
+fn list_copy(args: &[Value]) -> Result<Value> {
+    let mut elements = vec![];
+    let mut current = args[0].clone();
+    while let Value::Pair(pair) = current {
+        elements.push(pair.car());
+        current = pair.cdr();
+    }
+    Ok(elements.into_iter().rev().fold(current, |cdr, car| Value::cons(car, cdr)))
+}
+
+fn member(args: &[Value], equivalent: fn(&Value, &Value) -> bool) -> Result<Value> {
+    let mut current = args[1].clone();
+    while let Value::Pair(pair) = current {
+        if equivalent(&args[0], &pair.car()) {
+            return Ok(Value::Pair(pair));
+        }
+        current = pair.cdr();
+    }
+    Ok(Value::Boolean(false))
+}
+
+fn assoc(args: &[Value], equivalent: fn(&Value, &Value) -> bool) -> Result<Value> {
+    for entry in list(args, 1)? {
+        match &entry {
+            Value::Pair(pair) if equivalent(&args[0], &pair.car()) => return Ok(entry),
+            Value::Pair(_) => (),
+            _ => return error!(RuntimeError::WrongType(1, "association list")),
+        }
+    }
+    Ok(Value::Boolean(false))
+}
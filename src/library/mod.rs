@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{
+    Environment, Value, Procedure, Control, Arity, Pair, Port, ErrorObject, ErrorKind, Printer, Style, RuntimeError,
+    Complex, Real, parse_number,
+};
+
+mod base;
+mod lists;
+mod numbers;
+mod sequences;
+mod ports;
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+type Function = fn(&[Value]) -> Result<Value>;
+
+pub(crate) fn install(environment: &Environment) {
+    base::install(environment);
+    numbers::install(environment);
+    lists::install(environment);
+    sequences::install(environment);
+    ports::install(environment);
+}
+
+fn define(environment: &Environment, name: &str, arity: Arity, function: Function) {
+    environment.define_native(name, arity, function);
+}
+
+// Argument accessors: each checks the type of `arguments[i]` and reports the
+// position and expected type otherwise.
+
+fn pair(arguments: &[Value], i: usize) -> Result<&Rc<Pair>> {
+    match &arguments[i] {
+        Value::Pair(pair) => Ok(pair),
+        _ => error!(RuntimeError::WrongType(i, "pair")),
+    }
+}
+
+fn list(arguments: &[Value], i: usize) -> Result<Vec<Value>> {
+    match arguments[i].list_to_vec() {
+        Some(elements) => Ok(elements),
+        None => error!(RuntimeError::WrongType(i, "list")),
+    }
+}
+
+fn string(arguments: &[Value], i: usize) -> Result<&Rc<RefCell<String>>> {
+    match &arguments[i] {
+        Value::String(s) => Ok(s),
+        _ => error!(RuntimeError::WrongType(i, "string")),
+    }
+}
+
+fn character(arguments: &[Value], i: usize) -> Result<char> {
+    match &arguments[i] {
+        Value::Character(c) => Ok(*c),
+        _ => error!(RuntimeError::WrongType(i, "character")),
+    }
+}
+
+fn symbol(arguments: &[Value], i: usize) -> Result<&str> {
+    match &arguments[i] {
+        Value::Symbol(s) => Ok(s),
+        _ => error!(RuntimeError::WrongType(i, "symbol")),
+    }
+}
+
+fn vector(arguments: &[Value], i: usize) -> Result<&Rc<RefCell<Vec<Value>>>> {
+    match &arguments[i] {
+        Value::Vector(v) => Ok(v),
+        _ => error!(RuntimeError::WrongType(i, "vector")),
+    }
+}
+
+fn bytevector(arguments: &[Value], i: usize) -> Result<&Rc<RefCell<Vec<u8>>>> {
+    match &arguments[i] {
+        Value::ByteVector(bytes) => Ok(bytes),
+        _ => error!(RuntimeError::WrongType(i, "bytevector")),
+    }
+}
+
+fn port(arguments: &[Value], i: usize) -> Result<&Rc<Port>> {
+    match &arguments[i] {
+        Value::Port(port) => Ok(port),
+        _ => error!(RuntimeError::WrongType(i, "port")),
+    }
+}
+
+fn error_object(arguments: &[Value], i: usize) -> Result<&Rc<ErrorObject>> {
+    match &arguments[i] {
+        Value::Error(error) => Ok(error),
+        _ => error!(RuntimeError::WrongType(i, "error object")),
+    }
+}
+
+// An exact non-negative integer, as used for lengths and indices.
+fn index(arguments: &[Value], i: usize) -> Result<usize> {
+    match &arguments[i] {
+        Value::Number(Complex::Real(Real::Integer(n))) if *n >= 0 => Ok(*n as usize),
+        _ => error!(RuntimeError::WrongType(i, "exact non-negative integer")),
+    }
+}
+
+fn byte(arguments: &[Value], i: usize) -> Result<u8> {
+    match &arguments[i] {
+        Value::Number(Complex::Real(Real::Integer(n))) if (0..=255).contains(n) => Ok(*n as u8),
+        _ => error!(RuntimeError::WrongType(i, "byte")),
+    }
+}
+
+// Checks that `index` is below `length` and returns it.
+fn in_range(index: usize, length: usize) -> Result<usize> {
+    if index < length {
+        Ok(index)
+    } else {
+        error!(RuntimeError::IndexOutOfRange(index))
+    }
+}
+
+fn integer(n: usize) -> Value {
+    Value::Number(Complex::Real(Real::Integer(n as i64)))
+}
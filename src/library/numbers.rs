@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+
+use super::{define, string, parse_number, Environment, Value, Arity, RuntimeError, Complex, Real, Result};
+
+// 6.2 Numbers: the numerical tower of the model's `Complex` and `Real`.
+pub fn install(environment: &Environment) {
+    // 6.2.6 numerical type predicates
+    define(environment, "number?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Number(_)))));
+    define(environment, "complex?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Number(_)))));
+    define(environment, "real?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Number(Complex::Real(_))))));
+    define(environment, "rational?", Arity::Exactly(1), |args| Ok(Value::Boolean(match &args[0] {
+        Value::Number(Complex::Real(real)) => !real.is_nan() && !real.is_infinite(),
+        _ => false,
+    })));
+    define(environment, "integer?", Arity::Exactly(1), |args| Ok(Value::Boolean(match &args[0] {
+        Value::Number(Complex::Real(real)) => real.is_integer(),
+        _ => false,
+    })));
+    define(environment, "exact-integer?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(
+        args[0],
+        Value::Number(Complex::Real(Real::Integer(_))) | Value::Number(Complex::Real(Real::BigInteger(_)))
+    ))));
+    define(environment, "exact?", Arity::Exactly(1), |args| Ok(Value::Boolean(number(args, 0)?.is_exact())));
+    define(environment, "inexact?", Arity::Exactly(1), |args| Ok(Value::Boolean(!number(args, 0)?.is_exact())));
+    define(environment, "nan?", Arity::Exactly(1), |args| Ok(Value::Boolean(number(args, 0)?.is_nan())));
+    define(environment, "infinite?", Arity::Exactly(1), |args| Ok(Value::Boolean(number(args, 0)?.is_infinite())));
+    define(environment, "finite?", Arity::Exactly(1), |args| Ok(Value::Boolean(number(args, 0)?.is_finite())));
+    define(environment, "zero?", Arity::Exactly(1), |args| Ok(Value::Boolean(number(args, 0)?.is_zero())));
+    define(environment, "positive?", Arity::Exactly(1), |args| Ok(Value::Boolean(real(args, 0)?.is_positive())));
+    define(environment, "negative?", Arity::Exactly(1), |args| Ok(Value::Boolean(real(args, 0)?.is_negative())));
+    define(environment, "odd?", Arity::Exactly(1), |args| {
+        let (_, remainder) = integer(args, 0)?.truncate_div(&Real::Integer(2)).unwrap();
+        Ok(Value::Boolean(!remainder.is_zero()))
+    });
+    define(environment, "even?", Arity::Exactly(1), |args| {
+        let (_, remainder) = integer(args, 0)?.truncate_div(&Real::Integer(2)).unwrap();
+        Ok(Value::Boolean(remainder.is_zero()))
+    });
+
+    // 6.2.6 comparisons
+    define(environment, "=", Arity::AtLeast(1), |args| {
+        let mut result = true;
+        for i in 1..args.len() {
+            result &= number(args, i - 1)?.equals(number(args, i)?);
+        }
+        number(args, args.len() - 1)?;
+        Ok(Value::Boolean(result))
+    });
+    define(environment, "<", Arity::AtLeast(1), |args| compare(args, |o| o == Ordering::Less));
+    define(environment, ">", Arity::AtLeast(1), |args| compare(args, |o| o == Ordering::Greater));
+    define(environment, "<=", Arity::AtLeast(1), |args| compare(args, |o| o != Ordering::Greater));
+    define(environment, ">=", Arity::AtLeast(1), |args| compare(args, |o| o != Ordering::Less));
+    define(environment, "max", Arity::AtLeast(1), |args| extremum(args, Ordering::Greater));
+    define(environment, "min", Arity::AtLeast(1), |args| extremum(args, Ordering::Less));
+
+    // 6.2.6 arithmetic
+    define(environment, "+", Arity::AtLeast(0), |args| match args.len() {
+        0 => Ok(from_real(Real::Integer(0))),
+        _ => fold(args, |a, b| Some(a + b)),
+    });
+    define(environment, "*", Arity::AtLeast(0), |args| match args.len() {
+        0 => Ok(from_real(Real::Integer(1))),
+        _ => fold(args, |a, b| Some(a * b)),
+    });
+    define(environment, "-", Arity::AtLeast(1), |args| match args.len() {
+        1 => number(args, 0).map(|n| Value::Number(-n)),
+        _ => fold(args, |a, b| Some(a - b)),
+    });
+    define(environment, "/", Arity::AtLeast(1), |args| match args.len() {
+        1 => Complex::Real(Real::Integer(1)).checked_div(number(args, 0)?)
+            .map(Value::Number)
+            .ok_or(RuntimeError::DivisionByZero),
+        _ => fold(args, Complex::checked_div),
+    });
+    define(environment, "abs", Arity::Exactly(1), |args| Ok(from_real(real(args, 0)?.abs())));
+    define(environment, "square", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)? * number(args, 0)?)));
+
+    // 6.2.6 integer division
+    define(environment, "floor/", Arity::Exactly(2), |args| {
+        let (q, r) = divide(args, Real::floor_div)?;
+        Ok(Value::values(vec![from_real(q), from_real(r)]))
+    });
+    define(environment, "floor-quotient", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::floor_div)?.0)));
+    define(environment, "floor-remainder", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::floor_div)?.1)));
+    define(environment, "modulo", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::floor_div)?.1)));
+    define(environment, "truncate/", Arity::Exactly(2), |args| {
+        let (q, r) = divide(args, Real::truncate_div)?;
+        Ok(Value::values(vec![from_real(q), from_real(r)]))
+    });
+    define(environment, "truncate-quotient", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::truncate_div)?.0)));
+    define(environment, "truncate-remainder", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::truncate_div)?.1)));
+    define(environment, "quotient", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::truncate_div)?.0)));
+    define(environment, "remainder", Arity::Exactly(2), |args| Ok(from_real(divide(args, Real::truncate_div)?.1)));
+    define(environment, "gcd", Arity::AtLeast(0), |args| {
+        let mut result = Real::Integer(0);
+        for i in 0..args.len() {
+            result = result.gcd(integer(args, i)?);
+        }
+        Ok(from_real(result))
+    });
+    define(environment, "lcm", Arity::AtLeast(0), |args| {
+        let mut result = Real::Integer(1);
+        for i in 0..args.len() {
+            result = result.lcm(integer(args, i)?);
+        }
+        Ok(from_real(result))
+    });
+
+    // 6.2.6 rationals and rounding
+    define(environment, "numerator", Arity::Exactly(1), |args| {
+        real(args, 0)?.numerator().map(from_real).ok_or(RuntimeError::WrongType(0, "rational number"))
+    });
+    define(environment, "denominator", Arity::Exactly(1), |args| {
+        real(args, 0)?.denominator().map(from_real).ok_or(RuntimeError::WrongType(0, "rational number"))
+    });
+    define(environment, "floor", Arity::Exactly(1), |args| Ok(from_real(real(args, 0)?.floor())));
+    define(environment, "ceiling", Arity::Exactly(1), |args| Ok(from_real(real(args, 0)?.ceiling())));
+    define(environment, "truncate", Arity::Exactly(1), |args| Ok(from_real(real(args, 0)?.truncate())));
+    define(environment, "round", Arity::Exactly(1), |args| Ok(from_real(real(args, 0)?.round())));
+
+    // 6.2.6 powers and exactness
+    define(environment, "expt", Arity::Exactly(2), |args| {
+        number(args, 0)?.expt(number(args, 1)?).map(Value::Number)
+    });
+    define(environment, "exact-integer-sqrt", Arity::Exactly(1), |args| match real(args, 0)?.exact_integer_sqrt() {
+        Some((root, rest)) => Ok(Value::values(vec![from_real(root), from_real(rest)])),
+        None => error!(RuntimeError::WrongType(0, "exact non-negative integer")),
+    });
+    for name in ["exact", "inexact->exact"] {
+        define(environment, name, Arity::Exactly(1), |args| {
+            number(args, 0)?.to_exact().map(Value::Number).ok_or(RuntimeError::WrongType(0, "finite number"))
+        });
+    }
+    for name in ["inexact", "exact->inexact"] {
+        define(environment, name, Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.to_inexact())));
+    }
+
+    // 6.2.6 transcendental functions
+    define(environment, "sqrt", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.sqrt())));
+    define(environment, "exp", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.exp())));
+    define(environment, "log", Arity::Between(1, 2), |args| match args.len() {
+        1 => Ok(Value::Number(number(args, 0)?.log())),
+        // the logarithm in base `args[1]`
+        _ => number(args, 0)?.log().checked_div(&number(args, 1)?.log())
+            .map(Value::Number)
+            .ok_or(RuntimeError::DivisionByZero),
+    });
+    define(environment, "sin", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.sin())));
+    define(environment, "cos", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.cos())));
+    define(environment, "tan", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.tan())));
+    define(environment, "asin", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.asin())));
+    define(environment, "acos", Arity::Exactly(1), |args| Ok(Value::Number(number(args, 0)?.acos())));
+    define(environment, "atan", Arity::Between(1, 2), |args| match args.len() {
+        1 => Ok(Value::Number(number(args, 0)?.atan())),
+        _ => Ok(from_real(real(args, 0)?.atan2(real(args, 1)?))),
+    });
+
+    // 6.2.6 complex numbers
+    define(environment, "make-rectangular", Arity::Exactly(2), |args| {
+        Ok(Value::Number(Complex::new(real(args, 0)?.clone(), real(args, 1)?.clone())))
+    });
+    define(environment, "make-polar", Arity::Exactly(2), |args| Ok(Value::Number(Complex::polar(real(args, 0)?, real(args, 1)?))));
+    define(environment, "real-part", Arity::Exactly(1), |args| Ok(from_real(number(args, 0)?.real_part())));
+    define(environment, "imag-part", Arity::Exactly(1), |args| Ok(from_real(number(args, 0)?.imag_part())));
+    define(environment, "magnitude", Arity::Exactly(1), |args| Ok(from_real(number(args, 0)?.magnitude())));
+    define(environment, "angle", Arity::Exactly(1), |args| Ok(from_real(number(args, 0)?.angle())));
+
+    // 6.2.7 numerical input and output
+    define(environment, "number->string", Arity::Between(1, 2), |args| {
+        let radix = if args.len() == 2 { radix(args, 1)? } else { 10 };
+        match number(args, 0)?.to_string_radix(radix) {
+            Some(text) => Ok(Value::string(&text)),
+            None => error!(RuntimeError::WrongType(0, "exact number")),
+        }
+    });
+    define(environment, "string->number", Arity::Between(1, 2), |args| {
+        let radix = if args.len() == 2 { radix(args, 1)? } else { 10 };
+        Ok(parse_number(&string(args, 0)?.borrow(), radix).map_or(Value::Boolean(false), Value::Number))
+    });
+}
+
+fn number(args: &[Value], i: usize) -> Result<&Complex> {
+    match &args[i] {
+        Value::Number(complex) => Ok(complex),
+        _ => error!(RuntimeError::WrongType(i, "number")),
+    }
+}
+
+fn real(args: &[Value], i: usize) -> Result<&Real> {
+    match &args[i] {
+        Value::Number(Complex::Real(real)) => Ok(real),
+        _ => error!(RuntimeError::WrongType(i, "real number")),
+    }
+}
+
+fn integer(args: &[Value], i: usize) -> Result<&Real> {
+    match &args[i] {
+        Value::Number(Complex::Real(real)) if real.is_integer() => Ok(real),
+        _ => error!(RuntimeError::WrongType(i, "integer")),
+    }
+}
+
+fn radix(args: &[Value], i: usize) -> Result<u32> {
+    match &args[i] {
+        Value::Number(Complex::Real(Real::Integer(radix @ (2 | 8 | 10 | 16)))) => Ok(*radix as u32),
+        _ => error!(RuntimeError::WrongType(i, "radix")),
+    }
+}
+
+fn from_real(real: Real) -> Value {
+    Value::Number(Complex::Real(real))
+}
+
+// Combines the arguments from left to right; `None` is a division by zero.
+fn fold(args: &[Value], operation: fn(&Complex, &Complex) -> Option<Complex>) -> Result<Value> {
+    let mut result = number(args, 0)?.clone();
+    for i in 1..args.len() {
+        result = operation(&result, number(args, i)?).ok_or(RuntimeError::DivisionByZero)?;
+    }
+    Ok(Value::Number(result))
+}
+
+fn divide(args: &[Value], division: fn(&Real, &Real) -> Option<(Real, Real)>) -> Result<(Real, Real)> {
+    division(integer(args, 0)?, integer(args, 1)?).ok_or(RuntimeError::DivisionByZero)
+}
+
+fn compare(args: &[Value], test: fn(Ordering) -> bool) -> Result<Value> {
+    let mut result = true;
+    for i in 1..args.len() {
+        // NaN is not ordered, so every comparison with it is false
+        result &= real(args, i - 1)?.compare(real(args, i)?).is_some_and(test);
+    }
+    real(args, args.len() - 1)?;
+    Ok(Value::Boolean(result))
+}
+
+// `max` or `min`, inexact when any argument is.
+fn extremum(args: &[Value], wanted: Ordering) -> Result<Value> {
+    let mut result = real(args, 0)?.clone();
+    let mut exact = result.is_exact();
+    for i in 1..args.len() {
+        let current = real(args, i)?;
+        exact &= current.is_exact();
+        if current.is_nan() || current.compare(&result) == Some(wanted) {
+            result = current.clone();
+        }
+    }
+    Ok(from_real(if exact { result } else { result.to_inexact() }))
+}
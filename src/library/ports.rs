@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use super::{define, port, string, character, Environment, Value, Port, ErrorObject, Printer, Style, Arity, RuntimeError, Result};
+
+// 6.13 Input and output
+pub fn install(environment: &Environment) {
+    define(environment, "port?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Port(_)))));
+    define(environment, "input-port?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(&args[0], Value::Port(p) if p.is_input()))));
+    define(environment, "output-port?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(&args[0], Value::Port(p) if p.is_output()))));
+    define(environment, "textual-port?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(&args[0], Value::Port(p) if p.is_textual()))));
+    define(environment, "binary-port?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(&args[0], Value::Port(p) if p.is_binary()))));
+    define(environment, "input-port-open?", Arity::Exactly(1), |args| {
+        let port = port(args, 0)?;
+        Ok(Value::Boolean(port.is_input() && port.is_open()))
+    });
+    define(environment, "output-port-open?", Arity::Exactly(1), |args| {
+        let port = port(args, 0)?;
+        Ok(Value::Boolean(port.is_output() && port.is_open()))
+    });
+    define(environment, "close-port", Arity::Exactly(1), |args| {
+        port(args, 0)?.close();
+        Ok(Value::Unspecified)
+    });
+
+    define(environment, "open-input-string", Arity::Exactly(1), |args| {
+        Ok(Value::Port(Rc::new(Port::input_string(&string(args, 0)?.borrow()))))
+    });
+    define(environment, "open-input-file", Arity::Exactly(1), |args| {
+        let filename = string(args, 0)?.borrow().clone();
+        match std::fs::read_to_string(&filename) {
+            Ok(text) => Ok(Value::Port(Rc::new(Port::input_string(&text)))),
+            Err(_) => error!(RuntimeError::Raised(Value::Error(Rc::new(ErrorObject::file("cannot open file", &filename))))),
+        }
+    });
+    define(environment, "open-output-string", Arity::Exactly(0), |_| Ok(Value::Port(Rc::new(Port::output_string()))));
+    define(environment, "get-output-string", Arity::Exactly(1), |args| {
+        match port(args, 0)?.output_string_contents() {
+            Some(s) => Ok(Value::string(&s)),
+            None => error!(RuntimeError::WrongType(0, "string output port")),
+        }
+    });
+
+    define(environment, "read", Arity::Exactly(1), |args| match textual_input(args)?.read_datum() {
+        None => Ok(Value::Eof),
+        Some(Ok(datum)) => Ok(Value::from(&datum)),
+        Some(Err(error)) => error!(RuntimeError::Raised(Value::Error(Rc::new(ErrorObject::read(error))))),
+    });
+    define(environment, "read-char", Arity::Exactly(1), |args| Ok(textual_input(args)?.read_char().map_or(Value::Eof, Value::Character)));
+    define(environment, "peek-char", Arity::Exactly(1), |args| Ok(textual_input(args)?.peek_char().map_or(Value::Eof, Value::Character)));
+
+    // the port argument defaults to the standard output
+    let stdout = Rc::new(Port::writer(std::io::stdout()));
+    let current = stdout.clone();
+    environment.define_native("current-output-port", Arity::Exactly(0), move |_| Ok(Value::Port(current.clone())));
+    for (name, style) in [
+        ("write", Style::Write),
+        ("write-shared", Style::WriteShared),
+        ("write-simple", Style::WriteSimple),
+        ("display", Style::Display),
+    ] {
+        let stdout = stdout.clone();
+        environment.define_native(name, Arity::Between(1, 2), move |args| {
+            write_to(args, 1, &stdout, &Printer::new(&args[0], style).to_string())
+        });
+    }
+    let default = stdout.clone();
+    environment.define_native("write-char", Arity::Between(1, 2), move |args| {
+        write_to(args, 1, &default, &character(args, 0)?.to_string())
+    });
+    let default = stdout.clone();
+    environment.define_native("write-string", Arity::Between(1, 2), move |args| {
+        write_to(args, 1, &default, &string(args, 0)?.borrow())
+    });
+    environment.define_native("newline", Arity::Between(0, 1), move |args| write_to(args, 0, &stdout, "\n"));
+
+    define(environment, "eof-object", Arity::Exactly(0), |_| Ok(Value::Eof));
+    define(environment, "eof-object?", Arity::Exactly(1), |args| Ok(Value::Boolean(matches!(args[0], Value::Eof))));
+}
+
+fn textual_input(args: &[Value]) -> Result<&Rc<Port>> {
+    match port(args, 0)? {
+        port if port.is_input() && port.is_textual() && port.is_open() => Ok(port),
+        _ => error!(RuntimeError::WrongType(0, "open textual input port")),
+    }
+}
+
+// Writes to the port at `args[i]` if given, to `default` otherwise.
+fn write_to(args: &[Value], i: usize, default: &Port, text: &str) -> Result<Value> {
+    let port = match args.get(i) {
+        Some(_) => port(args, i)?,
+        None => default,
+    };
+    if port.write_str(text) {
+        Ok(Value::Unspecified)
+    } else {
+        error!(RuntimeError::WrongType(i, "open textual output port"))
+    }
+}
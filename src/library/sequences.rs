@@ -0,0 +1,113 @@
+use super::{
+    define, string, character, vector, bytevector, list, index, byte, in_range, integer,
+    Environment, Value, Arity, RuntimeError, Result,
+};
+
+pub fn install(environment: &Environment) {
+    // 6.7 Strings
+    define(environment, "make-string", Arity::Between(1, 2), make_string);
+    define(environment, "string", Arity::AtLeast(0), |args| {
+        (0..args.len()).map(|i| character(args, i)).collect::<Result<String>>().map(|s| Value::string(&s))
+    });
+    define(environment, "string-length", Arity::Exactly(1), |args| Ok(integer(string(args, 0)?.borrow().chars().count())));
+    define(environment, "string-ref", Arity::Exactly(2), string_ref);
+    define(environment, "string-set!", Arity::Exactly(3), string_set);
+    define(environment, "string=?", Arity::AtLeast(1), string_equal);
+    define(environment, "string-append", Arity::AtLeast(0), |args| {
+        let mut result = String::new();
+        for i in 0..args.len() {
+            result.push_str(&string(args, i)?.borrow());
+        }
+        Ok(Value::string(&result))
+    });
+    define(environment, "string-copy", Arity::Exactly(1), |args| Ok(Value::string(&string(args, 0)?.borrow())));
+    define(environment, "string->list", Arity::Exactly(1), |args| {
+        Ok(Value::list(string(args, 0)?.borrow().chars().map(Value::Character).collect::<Vec<_>>()))
+    });
+    define(environment, "list->string", Arity::Exactly(1), |args| {
+        let characters = list(args, 0)?;
+        (0..characters.len()).map(|i| character(&characters, i)).collect::<Result<String>>()
+            .map(|s| Value::string(&s))
+            .map_err(|_| RuntimeError::WrongType(0, "list of characters"))
+    });
+
+    // 6.8 Vectors
+    define(environment, "make-vector", Arity::Between(1, 2), |args| {
+        let fill = args.get(1).cloned().unwrap_or(Value::Unspecified);
+        Ok(Value::vector(vec![fill; index(args, 0)?]))
+    });
+    define(environment, "vector", Arity::AtLeast(0), |args| Ok(Value::vector(args.to_vec())));
+    define(environment, "vector-length", Arity::Exactly(1), |args| Ok(integer(vector(args, 0)?.borrow().len())));
+    define(environment, "vector-ref", Arity::Exactly(2), |args| {
+        let vector = vector(args, 0)?.borrow();
+        Ok(vector[in_range(index(args, 1)?, vector.len())?].clone())
+    });
+    define(environment, "vector-set!", Arity::Exactly(3), |args| {
+        let mut vector = vector(args, 0)?.borrow_mut();
+        let k = in_range(index(args, 1)?, vector.len())?;
+        vector[k] = args[2].clone();
+        Ok(Value::Unspecified)
+    });
+    define(environment, "vector->list", Arity::Exactly(1), |args| Ok(Value::list(vector(args, 0)?.borrow().clone())));
+    define(environment, "list->vector", Arity::Exactly(1), |args| Ok(Value::vector(list(args, 0)?)));
+    define(environment, "vector-fill!", Arity::Exactly(2), |args| {
+        vector(args, 0)?.borrow_mut().iter_mut().for_each(|slot| *slot = args[1].clone());
+        Ok(Value::Unspecified)
+    });
+
+    // 6.9 Bytevectors
+    define(environment, "make-bytevector", Arity::Between(1, 2), |args| {
+        let fill = if args.len() > 1 { byte(args, 1)? } else { 0 };
+        Ok(Value::bytevector(vec![fill; index(args, 0)?]))
+    });
+    define(environment, "bytevector", Arity::AtLeast(0), |args| {
+        (0..args.len()).map(|i| byte(args, i)).collect::<Result<Vec<_>>>().map(Value::bytevector)
+    });
+    define(environment, "bytevector-length", Arity::Exactly(1), |args| Ok(integer(bytevector(args, 0)?.borrow().len())));
+    define(environment, "bytevector-u8-ref", Arity::Exactly(2), |args| {
+        let bytes = bytevector(args, 0)?.borrow();
+        Ok(integer(bytes[in_range(index(args, 1)?, bytes.len())?] as usize))
+    });
+    define(environment, "bytevector-u8-set!", Arity::Exactly(3), |args| {
+        let mut bytes = bytevector(args, 0)?.borrow_mut();
+        let k = in_range(index(args, 1)?, bytes.len())?;
+        bytes[k] = byte(args, 2)?;
+        Ok(Value::Unspecified)
+    });
+}
+
+fn make_string(args: &[Value]) -> Result<Value> {
+    let length = index(args, 0)?;
+    let fill = if args.len() > 1 { character(args, 1)? } else { ' ' };
+    Ok(Value::string(&std::iter::repeat_n(fill, length).collect::<String>()))
+}
+
+fn string_ref(args: &[Value]) -> Result<Value> {
+    let s = string(args, 0)?.borrow();
+    let k = index(args, 1)?;
+    match s.chars().nth(k) {
+        Some(c) => Ok(Value::Character(c)),
+        None => error!(RuntimeError::IndexOutOfRange(k)),
+    }
+}
+
+fn string_set(args: &[Value]) -> Result<Value> {
+    let mut s = string(args, 0)?.borrow_mut();
+    let k = index(args, 1)?;
+    let c = character(args, 2)?;
+    let mut characters = s.chars().collect::<Vec<_>>();
+    let k = in_range(k, characters.len())?;
+    characters[k] = c;
+    *s = characters.into_iter().collect();
+    Ok(Value::Unspecified)
+}
+
+fn string_equal(args: &[Value]) -> Result<Value> {
+    let first = string(args, 0)?.borrow().clone();
+    for i in 1..args.len() {
+        if *string(args, i)?.borrow() != first {
+            return Ok(Value::Boolean(false));
+        }
+    }
+    Ok(Value::Boolean(true))
+}
@@ -1,4 +1,6 @@
-use super::{Primitive, ToLocated, GenericPair, Located, Real};
+use std::fmt;
+
+use super::{Primitive, ToLocated, GenericPair, Located};
 
 pub type DatumPair = GenericPair<Located<Datum>>;
 
@@ -12,7 +14,112 @@ pub enum Datum {
     // compound datum
     Pair(Box<DatumPair>), // List
     Vector(Vec<Located<Datum>>),
-    
+
+    // 2.4 datum labels: `#n=` names the datum it prefixes, and `#n#` within
+    // it or after it stands for that same object, which is how shared and
+    // circular structure is written
+    Labelled(usize, Box<Located<Datum>>),
+    Reference(usize),
+}
+
+impl ToLocated for Datum {}
+
+// The external representation (as `write` prints it), which the processor
+// reads back as an equal datum.
+impl fmt::Display for Datum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Datum::Primitive(p) => write!(f, "{}", p),
+            Datum::Symbol(s) => write_symbol(f, s),
+            Datum::ByteVector(bytes) => {
+                write!(f, "#u8(")?;
+                for (i, b) in bytes.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 {""} else {" "}, b)?;
+                }
+                write!(f, ")")
+            },
+            Datum::Vector(data) => {
+                write!(f, "#(")?;
+                for (i, d) in data.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 {""} else {" "}, d.data)?;
+                }
+                write!(f, ")")
+            },
+            Datum::Labelled(n, datum) => write!(f, "#{}={}", n, datum.data),
+            Datum::Reference(n) => write!(f, "#{}#", n),
+            Datum::Pair(pair) => match pair.as_ref() {
+                DatumPair::Empty => write!(f, "()"),
+                DatumPair::Some(car, cdr) => {
+                    if let Some((prefix, datum)) = abbreviation(car, cdr) {
+                        return write!(f, "{}{}", prefix, datum);
+                    }
+                    write!(f, "({}", car.data)?;
+                    let mut rest = &cdr.data;
+                    while let Datum::Pair(pair) = rest {
+                        match pair.as_ref() {
+                            DatumPair::Some(car, cdr) => {
+                                write!(f, " {}", car.data)?;
+                                rest = &cdr.data;
+                            },
+                            DatumPair::Empty => return write!(f, ")"),
+                        }
+                    }
+                    write!(f, " . {})", rest)
+                },
+            },
+        }
+    }
 }
 
-impl ToLocated for Datum {}
\ No newline at end of file
+// `(quote x)` and its siblings print as `'x` and so on.
+pub(crate) fn abbreviation<'a>(car: &Located<Datum>, cdr: &'a Located<Datum>) -> Option<(&'static str, &'a Datum)> {
+    let prefix = match &car.data {
+        Datum::Symbol(s) if s == "quote" => "'",
+        Datum::Symbol(s) if s == "quasiquote" => "`",
+        Datum::Symbol(s) if s == "unquote" => ",",
+        Datum::Symbol(s) if s == "unquote-splicing" => ",@",
+        _ => return None,
+    };
+    match &cdr.data {
+        Datum::Pair(pair) => match pair.as_ref() {
+            DatumPair::Some(datum, Located{data: Datum::Pair(rest), ..}) if **rest == DatumPair::Empty => Some((prefix, &datum.data)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Symbols that would not read back as themselves are written between bars.
+pub(crate) fn write_symbol(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if is_plain_identifier(name) {
+        return write!(f, "{}", name);
+    }
+    write!(f, "|")?;
+    for c in name.chars() {
+        match c {
+            '|' => write!(f, "\\|")?,
+            '\\' => write!(f, "\\\\")?,
+            c if c.is_control() => write!(f, "\\x{:x};", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "|")
+}
+
+fn is_plain_identifier(name: &str) -> bool {
+    let initial = |c: char| c.is_ascii_alphabetic() || "!$%&*/:<=>?@^_~".contains(c);
+    let subsequent = |c: char| initial(c) || c.is_ascii_digit() || "+-.@".contains(c);
+    let sign_subsequent = |c: char| initial(c) || "+-@".contains(c);
+
+    // these read as numbers
+    if matches!(name, "+i" | "-i" | "+inf.0" | "-inf.0" | "+nan.0" | "-nan.0") {
+        return false;
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), _) if initial(c) => name.chars().all(subsequent),
+        (Some('+'), None) | (Some('-'), None) => true,
+        (Some('+'), Some(c)) | (Some('-'), Some(c)) if sign_subsequent(c) => chars.all(subsequent),
+        _ => name == "...",
+    }
+}
@@ -1,14 +1,17 @@
-use super::{ToLocated, Token};
+use std::fmt;
+
+use super::{ToLocated, Token, Located};
+use crate::{Arity, Value};
 
 macro_rules! error {
     ($arg:expr) => {
-        Err($arg);
+        Err($arg)
     };
 }
 
 macro_rules! located_error {
     ($arg:expr, $loc:expr) => {
-        Err($arg.with_location($loc));
+        Err($arg.with_location($loc))
     };
 }
 
@@ -17,10 +20,37 @@ pub enum LexerError {
     UnexpectedBegin,
     UnexpectedEnd,
     UnrecognizedToken,
+    // a digit outside the radix, a decimal point or exponent outside radix
+    // 10, missing digits, or a zero denominator
+    InvalidNumber,
+    // an unknown character name, or a hex scalar value that is not a
+    // character
+    InvalidCharacter,
+    // an unknown escape in a string or `|...|` identifier, a hex escape that
+    // is not a character, or a backslash followed by spaces but no newline
+    InvalidEscape,
+    // a number, boolean, character or `#n#` run into the next token
+    ExpectedDelimiter,
 }
 
 impl ToLocated for LexerError {}
 
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LexerError::UnexpectedBegin => "unexpected character",
+            LexerError::UnexpectedEnd => "unexpected end of input",
+            LexerError::UnrecognizedToken => "unrecognized token",
+            LexerError::InvalidNumber => "invalid number",
+            LexerError::InvalidCharacter => "invalid character",
+            LexerError::InvalidEscape => "invalid escape",
+            LexerError::ExpectedDelimiter => "missing delimiter",
+        })
+    }
+}
+
+impl std::error::Error for LexerError {}
+
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum ProcessorError {
@@ -28,7 +58,146 @@ pub enum ProcessorError {
     UnmatchedParentheses,
     UnexpectedEnd,
     UnexpectedToken(Token),
+    // `#n#` before any `#n=` in the same top-level datum, or `#n=#n#`
+    UndefinedLabel(usize),
 }
 
 impl ToLocated for ProcessorError {}
 
+impl fmt::Display for ProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessorError::LexerError(e) => write!(f, "{}", e),
+            ProcessorError::UnmatchedParentheses => write!(f, "unmatched parenthesis"),
+            ProcessorError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ProcessorError::UnexpectedToken(_) => write!(f, "unexpected token"),
+            ProcessorError::UndefinedLabel(_) => write!(f, "undefined datum label"),
+        }
+    }
+}
+
+impl std::error::Error for ProcessorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProcessorError::LexerError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum RuntimeError {
+    ProcessorError(ProcessorError),
+    BadSyntax(String),
+    UnboundVariable(String),
+    UndefinedAssignment(String),
+    NotProcedure(Value),
+    WrongArgumentCount(Arity, usize),
+    // position (from 0) of the offending argument and the expected type
+    WrongType(usize, &'static str),
+    IndexOutOfRange(usize),
+    // an exact number divided by exact zero
+    DivisionByZero,
+    // an exact result too large to compute
+    NumberTooLarge,
+    // the continuation stack grew past the interpreter's limit
+    RecursionLimitExceeded,
+    // an object raised by `raise` or `error` that no handler caught
+    Raised(Value),
+    // a handler returned from a non-continuable `raise` of the value
+    HandlerReturned(Value),
+}
+
+impl ToLocated for RuntimeError {}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::ProcessorError(e) => write!(f, "{}", e),
+            RuntimeError::BadSyntax(keyword) => write!(f, "bad syntax in `{}`", keyword),
+            RuntimeError::UnboundVariable(name) => write!(f, "unbound variable `{}`", name),
+            RuntimeError::UndefinedAssignment(name) => write!(f, "assignment to undefined variable `{}`", name),
+            RuntimeError::NotProcedure(value) => write!(f, "not a procedure: {}", value),
+            RuntimeError::WrongArgumentCount(arity, count) => {
+                write!(f, "wrong number of arguments: expected {}, got {}", arity, count)
+            },
+            RuntimeError::WrongType(i, expected) => write!(f, "wrong type of argument {}: expected {}", i + 1, expected),
+            RuntimeError::IndexOutOfRange(k) => write!(f, "index {} out of range", k),
+            RuntimeError::DivisionByZero => write!(f, "division by zero"),
+            RuntimeError::NumberTooLarge => write!(f, "exact number too large"),
+            RuntimeError::RecursionLimitExceeded => write!(f, "recursion limit exceeded"),
+            // the message and irritants of an error object, as `error` was
+            // given them
+            RuntimeError::Raised(Value::Error(error)) => {
+                write!(f, "{}", error.message)?;
+                error.irritants.iter().try_for_each(|irritant| write!(f, " {}", irritant))
+            },
+            RuntimeError::Raised(value) => write!(f, "uncaught exception: {}", value),
+            RuntimeError::HandlerReturned(value) => write!(f, "handler returned from non-continuable raise of {}", value),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RuntimeError::ProcessorError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// Why `Interpreter::load` failed: the file could not be read, which happens
+// before there is any source to locate the error in, or evaluating it did.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(String, std::io::Error),
+    Runtime(Located<RuntimeError>),
+}
+
+impl From<Located<RuntimeError>> for LoadError {
+    fn from(error: Located<RuntimeError>) -> Self {
+        LoadError::Runtime(error)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "cannot read {}: {}", path, e),
+            LoadError::Runtime(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(_, e) => Some(e),
+            LoadError::Runtime(e) => Some(e),
+        }
+    }
+}
+
+// The line and column, counted from one, then the error. A located error
+// knows its file only by id, so the path and source line are left to
+// `Diagnostic::render`, which is given the `SourceMap` to look them up in.
+macro_rules! located_display {
+    ($($error:ty),*) => {$(
+        impl fmt::Display for Located<$error> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let location = self.location();
+                write!(f, "{}:{}: {}", location.row + 1, location.col + 1, self.data)
+            }
+        }
+
+        impl std::error::Error for Located<$error> {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                std::error::Error::source(&self.data)
+            }
+        }
+    )*};
+}
+
+located_display!(LexerError, ProcessorError, RuntimeError);
@@ -1,25 +1,57 @@
 use std::ops::{Deref, DerefMut};
 
+use super::FileId;
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub struct Located<T> {
     pub data: T,
-    pub location: Location,
+    pub span: Span,
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+// A point in the source: the row and column count chars from zero, the
+// offset counts bytes from the start of the file.
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
 pub struct Location {
+    pub file: FileId,
     pub row: u32,
     pub col: u32,
+    pub offset: usize,
+}
+
+// The source from `start` up to but excluding `end`.
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    // The empty span at `location`, for what has a start but no extent.
+    pub fn at(location: Location) -> Self {
+        Span{start: location, end: location}
+    }
+
+    // The byte range of the span, for slicing the source text.
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
 }
 
 pub trait ToLocated {
     fn with_location(self, location: Location) -> Located<Self>
+    where
+        Self: Sized,
+    {
+        self.with_span(Span::at(location))
+    }
+
+    fn with_span(self, span: Span) -> Located<Self>
     where
         Self: Sized,
     {
         Located::<Self> {
             data: self,
-            location: location,
+            span,
         }
     }
 }
@@ -30,6 +62,11 @@ impl<T> Located<T> {
     pub fn extract(self) -> T {
         self.data
     }
+
+    // Where the located thing starts.
+    pub fn location(&self) -> Location {
+        self.span.start
+    }
 }
 
 impl<T> Deref for Located<T> {
@@ -44,4 +81,4 @@ impl<T> DerefMut for Located<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.data
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,14 @@
 mod token;
 pub use token::*;
 
+mod number;
+
 mod located;
 pub use located::*;
 
+mod source;
+pub use source::*;
+
 #[macro_use]
 mod error;
 pub use error::*;
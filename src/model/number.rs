@@ -0,0 +1,734 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use super::{Complex, Real, RuntimeError};
+
+// The most bits an exact power may take; larger ones would take too long to
+// compute, if they fit in memory at all. Powers of other complex numbers than
+// Gaussian integers reduce fractions at every step, and so get a lower limit.
+const MAX_EXACT_BITS: u64 = 1 << 20;
+const MAX_EXACT_COMPLEX_BITS: u64 = 1 << 15;
+
+// 6.2 Numerical operations on the model types. Exact operands give exact
+// results and any inexact operand makes the result inexact; flonum
+// infinities and NaNs behave as in IEEE 754.
+
+impl Real {
+    // The normalized form of a flonum: infinities and NaNs are kept as their
+    // own variants, and the sign of a computed NaN is not significant.
+    pub fn float(f: f64) -> Self {
+        match f {
+            f if f.is_nan() => Real::PosNan,
+            f64::INFINITY => Real::PosInf,
+            f64::NEG_INFINITY => Real::NegInf,
+            f => Real::Float(f),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Real::Integer(_) | Real::BigInteger(_) => true,
+            Real::Float(f) => f.fract() == 0.0,
+            _ => false,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self.to_rational() {
+            Some(r) => r.is_zero(),
+            None => self.to_f64() == 0.0,
+        }
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.compare(&Real::Integer(0)) == Some(Ordering::Greater)
+    }
+
+    pub fn is_nan(&self) -> bool {
+        !self.is_exact() && self.to_f64().is_nan()
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        !self.is_exact() && self.to_f64().is_infinite()
+    }
+
+    // The exact value of a finite number.
+    fn exact_value(&self) -> Option<BigRational> {
+        self.to_rational().or_else(|| BigRational::from_float(self.to_f64()))
+    }
+
+    // `None` for infinities and NaNs, which have no exact counterpart.
+    pub fn to_exact(&self) -> Option<Real> {
+        self.exact_value().map(Real::rational)
+    }
+
+    pub fn to_inexact(&self) -> Real {
+        match self.is_exact() {
+            true => Real::float(self.to_f64()),
+            false => self.clone(),
+        }
+    }
+
+    fn arithmetic(
+        &self,
+        other: &Real,
+        integer: fn(i64, i64) -> Option<i64>,
+        big: fn(&BigInt, &BigInt) -> BigInt,
+        rational: fn(&BigRational, &BigRational) -> BigRational,
+        float: fn(f64, f64) -> f64,
+    ) -> Real {
+        if let (Real::Integer(a), Real::Integer(b)) = (self, other) {
+            if let Some(result) = integer(*a, *b) {
+                return Real::Integer(result);
+            }
+        }
+        match (self.to_rational(), other.to_rational()) {
+            // integers skip the reduction of rationals, which is slow for
+            // large ones
+            (Some(a), Some(b)) if a.is_integer() && b.is_integer() => Real::integer(big(a.numer(), b.numer())),
+            (Some(a), Some(b)) => Real::rational(rational(&a, &b)),
+            _ => Real::float(float(self.to_f64(), other.to_f64())),
+        }
+    }
+
+    // `None` when dividing by exact zero.
+    pub fn checked_div(&self, other: &Real) -> Option<Real> {
+        match (self.to_rational(), other.to_rational()) {
+            (Some(_), Some(b)) if b.is_zero() => None,
+            (Some(a), Some(b)) => Some(Real::rational(a / b)),
+            _ => Some(Real::float(self.to_f64() / other.to_f64())),
+        }
+    }
+
+    // The numerical order, compared exactly; `None` when either is a NaN.
+    pub fn compare(&self, other: &Real) -> Option<Ordering> {
+        if let (Real::Integer(a), Real::Integer(b)) = (self, other) {
+            return Some(a.cmp(b));
+        }
+        match (self.exact_value(), other.exact_value()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+
+    pub fn abs(&self) -> Real {
+        match self.is_exact() {
+            true if self.is_negative() => -self,
+            true => self.clone(),
+            false => Real::float(self.to_f64().abs()),
+        }
+    }
+
+    // 6.2.6 floor/ and truncate/: the quotient and remainder of integers, or
+    // `None` when `other` is zero.
+    pub fn floor_div(&self, other: &Real) -> Option<(Real, Real)> {
+        self.integer_div(other, Integer::div_mod_floor)
+    }
+
+    pub fn truncate_div(&self, other: &Real) -> Option<(Real, Real)> {
+        self.integer_div(other, Integer::div_rem)
+    }
+
+    fn integer_div(&self, other: &Real, divide: fn(&BigInt, &BigInt) -> (BigInt, BigInt)) -> Option<(Real, Real)> {
+        debug_assert!(self.is_integer() && other.is_integer());
+        if other.is_zero() {
+            return None;
+        }
+        let (a, b) = (self.exact_value()?.to_integer(), other.exact_value()?.to_integer());
+        let (q, r) = divide(&a, &b);
+        let exact = self.is_exact() && other.is_exact();
+        Some((exactness(exact, Real::integer(q)), exactness(exact, Real::integer(r))))
+    }
+
+    // The greatest common divisor and least common multiple of integers,
+    // which are never negative.
+    pub fn gcd(&self, other: &Real) -> Real {
+        self.integer_operation(other, |a, b| a.gcd(b))
+    }
+
+    pub fn lcm(&self, other: &Real) -> Real {
+        self.integer_operation(other, |a, b| a.lcm(b))
+    }
+
+    fn integer_operation(&self, other: &Real, operation: fn(&BigInt, &BigInt) -> BigInt) -> Real {
+        debug_assert!(self.is_integer() && other.is_integer());
+        let (a, b) = (self.exact_value().unwrap().to_integer(), other.exact_value().unwrap().to_integer());
+        exactness(self.is_exact() && other.is_exact(), Real::integer(operation(&a, &b)))
+    }
+
+    // The numerator and denominator in lowest terms, of the same exactness;
+    // `None` for infinities and NaNs.
+    pub fn numerator(&self) -> Option<Real> {
+        let r = self.exact_value()?;
+        Some(exactness(self.is_exact(), Real::integer(r.numer().clone())))
+    }
+
+    pub fn denominator(&self) -> Option<Real> {
+        let r = self.exact_value()?;
+        Some(exactness(self.is_exact(), Real::integer(r.denom().clone())))
+    }
+
+    pub fn floor(&self) -> Real {
+        self.round_with(BigRational::floor, f64::floor)
+    }
+
+    pub fn ceiling(&self) -> Real {
+        self.round_with(BigRational::ceil, f64::ceil)
+    }
+
+    pub fn truncate(&self) -> Real {
+        self.round_with(BigRational::trunc, f64::trunc)
+    }
+
+    // Rounds to even when halfway between two integers.
+    pub fn round(&self) -> Real {
+        self.round_with(round_to_even, f64::round_ties_even)
+    }
+
+    fn round_with(&self, exact: fn(&BigRational) -> BigRational, float: fn(f64) -> f64) -> Real {
+        match self.to_rational() {
+            Some(r) => Real::rational(exact(&r)),
+            None => Real::float(float(self.to_f64())),
+        }
+    }
+
+    // The root and remainder of an exact non-negative integer.
+    pub fn exact_integer_sqrt(&self) -> Option<(Real, Real)> {
+        let n = match self {
+            Real::Integer(_) | Real::BigInteger(_) if !self.is_negative() => self.to_rational()?.to_integer(),
+            _ => return None,
+        };
+        let root = n.sqrt();
+        let rest = &n - &root * &root;
+        Some((Real::integer(root), Real::integer(rest)))
+    }
+}
+
+fn exactness(exact: bool, result: Real) -> Real {
+    match exact {
+        true => result,
+        false => result.to_inexact(),
+    }
+}
+
+fn round_to_even(r: &BigRational) -> BigRational {
+    let floor = r.floor();
+    let half = BigRational::new(BigInt::one(), BigInt::from(2));
+    match (r - &floor).cmp(&half) {
+        Ordering::Less => floor,
+        Ordering::Greater => floor + BigInt::one(),
+        Ordering::Equal if floor.to_integer().is_even() => floor,
+        Ordering::Equal => floor + BigInt::one(),
+    }
+}
+
+impl Add for &Real {
+    type Output = Real;
+
+    fn add(self, other: &Real) -> Real {
+        self.arithmetic(other, i64::checked_add, |a, b| a + b, |a, b| a + b, |a, b| a + b)
+    }
+}
+
+impl Sub for &Real {
+    type Output = Real;
+
+    fn sub(self, other: &Real) -> Real {
+        self.arithmetic(other, i64::checked_sub, |a, b| a - b, |a, b| a - b, |a, b| a - b)
+    }
+}
+
+impl Mul for &Real {
+    type Output = Real;
+
+    fn mul(self, other: &Real) -> Real {
+        self.arithmetic(other, i64::checked_mul, |a, b| a * b, |a, b| a * b, |a, b| a * b)
+    }
+}
+
+impl Neg for &Real {
+    type Output = Real;
+
+    fn neg(self) -> Real {
+        self.clone().reverse()
+    }
+}
+
+impl Complex {
+    // The normalized form: a real when the imaginary part is exact zero.
+    pub fn new(real: Real, imaginary: Real) -> Self {
+        let exact_zero = |r: &Real| r.is_exact() && r.is_zero();
+        match (exact_zero(&real), exact_zero(&imaginary)) {
+            (_, true) => Complex::Real(real),
+            (true, false) => Complex::Imaginary(imaginary),
+            (false, false) => Complex::Complex(real, imaginary),
+        }
+    }
+
+    pub fn real_part(&self) -> Real {
+        match self {
+            Complex::Real(real) | Complex::Complex(real, _) => real.clone(),
+            Complex::Imaginary(_) => Real::Integer(0),
+        }
+    }
+
+    pub fn imag_part(&self) -> Real {
+        match self {
+            Complex::Real(_) => Real::Integer(0),
+            Complex::Complex(_, imaginary) | Complex::Imaginary(imaginary) => imaginary.clone(),
+        }
+    }
+
+    fn parts(&self) -> Vec<&Real> {
+        match self {
+            Complex::Real(real) | Complex::Imaginary(real) => vec![real],
+            Complex::Complex(real, imaginary) => vec![real, imaginary],
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.parts().iter().all(|r| r.is_exact())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.parts().iter().all(|r| r.is_zero())
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.parts().iter().any(|r| r.is_nan())
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        self.parts().iter().any(|r| r.is_infinite())
+    }
+
+    pub fn is_finite(&self) -> bool {
+        !self.is_nan() && !self.is_infinite()
+    }
+
+    // `None` when a part is an infinity or NaN.
+    pub fn to_exact(&self) -> Option<Complex> {
+        Some(Complex::new(self.real_part().to_exact()?, self.imag_part().to_exact()?))
+    }
+
+    pub fn to_inexact(&self) -> Complex {
+        match self {
+            Complex::Real(real) => Complex::Real(real.to_inexact()),
+            Complex::Complex(real, imaginary) => Complex::Complex(real.to_inexact(), imaginary.to_inexact()),
+            Complex::Imaginary(imaginary) => Complex::Imaginary(imaginary.to_inexact()),
+        }
+    }
+
+    // Numerical equality, as `=` tests it.
+    pub fn equals(&self, other: &Complex) -> bool {
+        let equal = |a: Real, b: Real| a.compare(&b) == Some(Ordering::Equal);
+        equal(self.real_part(), other.real_part()) && equal(self.imag_part(), other.imag_part())
+    }
+
+    // `None` when dividing by exact zero.
+    pub fn checked_div(&self, other: &Complex) -> Option<Complex> {
+        if let (Complex::Real(a), Complex::Real(b)) = (self, other) {
+            return a.checked_div(b).map(Complex::Real);
+        }
+        let (a, b, c, d) = (self.real_part(), self.imag_part(), other.real_part(), other.imag_part());
+        let denominator = &(&c * &c) + &(&d * &d);
+        let real = &(&a * &c) + &(&b * &d);
+        let imaginary = &(&b * &c) - &(&a * &d);
+        Some(Complex::new(real.checked_div(&denominator)?, imaginary.checked_div(&denominator)?))
+    }
+
+    // 6.2.6 expt: exact for an exact base and exact integer power, otherwise
+    // the principal value. Exact zero to a negative power divides by zero,
+    // and an exact power too large to compute is refused.
+    pub fn expt(&self, power: &Complex) -> Result<Complex, RuntimeError> {
+        if let Complex::Real(n @ Real::Integer(_)) | Complex::Real(n @ Real::BigInteger(_)) = power {
+            if let (true, Some(n)) = (self.is_exact(), n.to_rational()) {
+                return self.exact_power(&n.to_integer());
+            }
+        }
+        if power.is_exact() && power.is_zero() {
+            return Ok(Complex::Real(Real::Integer(1)));
+        }
+        if self.is_zero() && power.real_part().is_positive() {
+            let exact = self.is_exact() && power.is_exact();
+            return Ok(Complex::Real(exactness(exact, Real::Integer(0))));
+        }
+        Ok(match (self, power) {
+            (Complex::Real(x), Complex::Real(y)) if !x.is_negative() || y.is_integer() => {
+                Complex::Real(Real::float(x.to_f64().powf(y.to_f64())))
+            },
+            _ => self.float_expt(power),
+        })
+    }
+
+    fn exact_power(&self, n: &BigInt) -> Result<Complex, RuntimeError> {
+        // each factor of any base but 0, 1, -1, +i and -i adds at least a bit
+        let parts = self.parts();
+        let bits = parts.iter()
+            .filter_map(|r| r.to_rational())
+            .map(|r| r.numer().bits().max(r.denom().bits()))
+            .max()
+            .unwrap_or(0);
+        if parts.len() > 1 || bits > 1 {
+            let gaussian = parts.iter().all(|r| r.is_integer()) && !n.is_negative();
+            let limit = match (self, gaussian) {
+                (Complex::Real(_), _) | (_, true) => MAX_EXACT_BITS,
+                _ => MAX_EXACT_COMPLEX_BITS,
+            };
+            let steps = n.magnitude().to_u64().unwrap_or(u64::MAX);
+            if steps.saturating_mul(bits.max(1)) > limit {
+                return Err(RuntimeError::NumberTooLarge);
+            }
+        }
+        // a rational in lowest terms stays so when raised to a power
+        if let (Complex::Real(r), Some(steps)) = (self, n.magnitude().to_u32()) {
+            let r = r.to_rational().unwrap();
+            let (numerator, denominator) = match n.is_negative() {
+                false => (r.numer().pow(steps), r.denom().pow(steps)),
+                true if r.is_zero() => return Err(RuntimeError::DivisionByZero),
+                true => (r.denom().pow(steps) * r.numer().signum().pow(steps), r.numer().abs().pow(steps)),
+            };
+            return Ok(Complex::Real(Real::rational(BigRational::new_raw(numerator, denominator))));
+        }
+
+        let mut result = Complex::Real(Real::Integer(1));
+        let mut square = self.clone();
+        let mut exponent = n.abs();
+        while !exponent.is_zero() {
+            if exponent.is_odd() {
+                result = &result * &square;
+            }
+            exponent >>= 1;
+            if !exponent.is_zero() {
+                square = &square * &square;
+            }
+        }
+        match n.is_negative() {
+            true => Complex::Real(Real::Integer(1)).checked_div(&result).ok_or(RuntimeError::DivisionByZero),
+            false => Ok(result),
+        }
+    }
+
+    // exp(w log z) in floating point.
+    fn float_expt(&self, power: &Complex) -> Complex {
+        let ((a, b), (c, d)) = (self.float_parts(), power.float_parts());
+        let (magnitude, angle) = (a.hypot(b).ln(), b.atan2(a));
+        let (x, y) = (c * magnitude - d * angle, c * angle + d * magnitude);
+        float_complex(x.exp() * y.cos(), x.exp() * y.sin())
+    }
+}
+
+impl Add for &Complex {
+    type Output = Complex;
+
+    fn add(self, other: &Complex) -> Complex {
+        if let (Complex::Real(a), Complex::Real(b)) = (self, other) {
+            return Complex::Real(a + b);
+        }
+        Complex::new(&self.real_part() + &other.real_part(), &self.imag_part() + &other.imag_part())
+    }
+}
+
+impl Sub for &Complex {
+    type Output = Complex;
+
+    fn sub(self, other: &Complex) -> Complex {
+        if let (Complex::Real(a), Complex::Real(b)) = (self, other) {
+            return Complex::Real(a - b);
+        }
+        Complex::new(&self.real_part() - &other.real_part(), &self.imag_part() - &other.imag_part())
+    }
+}
+
+impl Mul for &Complex {
+    type Output = Complex;
+
+    fn mul(self, other: &Complex) -> Complex {
+        if let (Complex::Real(a), Complex::Real(b)) = (self, other) {
+            return Complex::Real(a * b);
+        }
+        let (a, b, c, d) = (self.real_part(), self.imag_part(), other.real_part(), other.imag_part());
+        Complex::new(&(&a * &c) - &(&b * &d), &(&a * &d) + &(&b * &c))
+    }
+}
+
+impl Neg for &Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        match self {
+            Complex::Real(real) => Complex::Real(-real),
+            Complex::Complex(real, imaginary) => Complex::Complex(-real, -imaginary),
+            Complex::Imaginary(imaginary) => Complex::Imaginary(-imaginary),
+        }
+    }
+}
+
+// 6.2.6 Transcendental functions and the `(scheme complex)` accessors. The
+// exact cases R7RS mentions, such as `(exp 0)` or `(sqrt 16)`, stay exact;
+// everything else is computed in floating point, and a real argument gives
+// a non-real result where the function leaves the real line.
+impl Complex {
+    // `magnitude@angle`, exact only for an exact zero angle.
+    pub fn polar(magnitude: &Real, angle: &Real) -> Complex {
+        if angle.is_exact() && angle.is_zero() {
+            return Complex::Real(magnitude.clone());
+        }
+        let (r, theta) = (magnitude.to_f64(), angle.to_f64());
+        float_complex(r * theta.cos(), r * theta.sin())
+    }
+
+    pub fn magnitude(&self) -> Real {
+        match self {
+            Complex::Real(real) | Complex::Imaginary(real) => real.abs(),
+            Complex::Complex(real, imaginary) => {
+                let square = &(real * real) + &(imaginary * imaginary);
+                exact_sqrt(&square).unwrap_or_else(|| Real::float(real.to_f64().hypot(imaginary.to_f64())))
+            },
+        }
+    }
+
+    pub fn angle(&self) -> Real {
+        match self {
+            Complex::Real(real) if real.is_exact() && !real.is_negative() => Real::Integer(0),
+            _ => Real::float(self.imag_part().to_f64().atan2(self.real_part().to_f64())),
+        }
+    }
+
+    // The principal square root, exact when an exact argument has an exact
+    // root.
+    pub fn sqrt(&self) -> Complex {
+        if let Complex::Real(real) = self {
+            return match (exact_sqrt(&real.abs()), real.is_negative()) {
+                (Some(root), false) => Complex::Real(root),
+                (Some(root), true) => Complex::Imaginary(root),
+                (None, false) => Complex::Real(Real::float(real.to_f64().sqrt())),
+                (None, true) => Complex::Imaginary(Real::float((-real.to_f64()).sqrt())),
+            };
+        }
+        let (a, b) = self.float_parts();
+        let magnitude = a.hypot(b);
+        let root = float_complex(((magnitude + a) / 2.0).sqrt(), ((magnitude - a) / 2.0).sqrt().copysign(b));
+        match root.to_exact() {
+            Some(exact) if self.is_exact() && (&exact * &exact).equals(self) => exact,
+            _ => root,
+        }
+    }
+
+    pub fn exp(&self) -> Complex {
+        if self.is_exact() && self.is_zero() {
+            return Complex::Real(Real::Integer(1));
+        }
+        let (a, b) = self.float_parts();
+        match self {
+            Complex::Real(_) => Complex::Real(Real::float(a.exp())),
+            _ => float_complex(a.exp() * b.cos(), a.exp() * b.sin()),
+        }
+    }
+
+    // The principal logarithm; the logarithm of zero is `-inf.0`.
+    pub fn log(&self) -> Complex {
+        match self {
+            Complex::Real(Real::Integer(1)) => Complex::Real(Real::Integer(0)),
+            Complex::Real(real) if !real.is_negative() => Complex::Real(Real::float(real.to_f64().ln())),
+            _ => {
+                let (a, b) = self.float_parts();
+                float_complex(a.hypot(b).ln(), b.atan2(a))
+            },
+        }
+    }
+
+    pub fn sin(&self) -> Complex {
+        let (a, b) = self.float_parts();
+        match self {
+            _ if self.is_exact() && self.is_zero() => Complex::Real(Real::Integer(0)),
+            Complex::Real(_) => Complex::Real(Real::float(a.sin())),
+            _ => float_complex(a.sin() * b.cosh(), a.cos() * b.sinh()),
+        }
+    }
+
+    pub fn cos(&self) -> Complex {
+        let (a, b) = self.float_parts();
+        match self {
+            _ if self.is_exact() && self.is_zero() => Complex::Real(Real::Integer(1)),
+            Complex::Real(_) => Complex::Real(Real::float(a.cos())),
+            _ => float_complex(a.cos() * b.cosh(), -a.sin() * b.sinh()),
+        }
+    }
+
+    pub fn tan(&self) -> Complex {
+        let (a, b) = self.float_parts();
+        let denominator = (2.0 * a).cos() + (2.0 * b).cosh();
+        match self {
+            _ if self.is_exact() && self.is_zero() => Complex::Real(Real::Integer(0)),
+            Complex::Real(_) => Complex::Real(Real::float(a.tan())),
+            _ => float_complex((2.0 * a).sin() / denominator, (2.0 * b).sinh() / denominator),
+        }
+    }
+
+    pub fn asin(&self) -> Complex {
+        match self {
+            _ if self.is_exact() && self.is_zero() => Complex::Real(Real::Integer(0)),
+            Complex::Real(x) if within_unit(x) => Complex::Real(Real::float(x.to_f64().asin())),
+            // -i log(iz + sqrt(1 - z^2))
+            _ => {
+                let (one, i, z) = (float_complex(1.0, 0.0), float_complex(0.0, 1.0), self.to_inexact());
+                let root = (&one - &(&z * &z)).sqrt();
+                &(-&i) * &(&(&i * &z) + &root).log()
+            },
+        }
+    }
+
+    pub fn acos(&self) -> Complex {
+        match self {
+            Complex::Real(Real::Integer(1)) => Complex::Real(Real::Integer(0)),
+            Complex::Real(x) if within_unit(x) => Complex::Real(Real::float(x.to_f64().acos())),
+            // pi/2 - asin z
+            _ => &float_complex(std::f64::consts::FRAC_PI_2, 0.0) - &self.to_inexact().asin(),
+        }
+    }
+
+    pub fn atan(&self) -> Complex {
+        match self {
+            _ if self.is_exact() && self.is_zero() => Complex::Real(Real::Integer(0)),
+            Complex::Real(x) => Complex::Real(Real::float(x.to_f64().atan())),
+            // (log(1 + iz) - log(1 - iz)) / 2i
+            _ => {
+                let (one, i, z) = (float_complex(1.0, 0.0), float_complex(0.0, 1.0), self.to_inexact());
+                let iz = &i * &z;
+                let difference = &(&one + &iz).log() - &(&one - &iz).log();
+                &difference * &float_complex(0.0, -0.5)
+            },
+        }
+    }
+
+    fn float_parts(&self) -> (f64, f64) {
+        (self.real_part().to_f64(), self.imag_part().to_f64())
+    }
+}
+
+impl Real {
+    // The two-argument `atan`: the angle of the point (x, self).
+    pub fn atan2(&self, x: &Real) -> Real {
+        match self.is_exact() && self.is_zero() && x.is_exact() && x.is_positive() {
+            true => Real::Integer(0),
+            false => Real::float(self.to_f64().atan2(x.to_f64())),
+        }
+    }
+}
+
+fn float_complex(real: f64, imaginary: f64) -> Complex {
+    Complex::new(Real::float(real), Real::float(imaginary))
+}
+
+// The root of an exact non-negative rational whose root is also rational.
+fn exact_sqrt(real: &Real) -> Option<Real> {
+    let r = real.to_rational()?;
+    if r.is_negative() {
+        return None;
+    }
+    let (numerator, denominator) = (r.numer().sqrt(), r.denom().sqrt());
+    match &numerator * &numerator == *r.numer() && &denominator * &denominator == *r.denom() {
+        true => Some(Real::rational(BigRational::new(numerator, denominator))),
+        false => None,
+    }
+}
+
+// |x| <= 1, where the real inverse sine and cosine are defined.
+fn within_unit(x: &Real) -> bool {
+    x.abs().compare(&Real::Integer(1)) != Some(Ordering::Greater)
+}
+
+#[cfg(test)]
+fn ratio(numerator: i64, denominator: i64) -> Real {
+    Real::fraction(BigInt::from(numerator), BigInt::from(denominator)).unwrap()
+}
+
+#[test]
+fn contagion() {
+    let (one, half) = (Real::Integer(1), ratio(1, 2));
+    assert_eq!(&one + &half, Real::Ration(3, 2));
+    assert_eq!(&half + &half, Real::Integer(1));
+    assert_eq!(&ratio(2, 3) * &ratio(3, 4), Real::Ration(1, 2));
+    assert_eq!(&one + &Real::Float(0.5), Real::Float(1.5));
+    assert_eq!(&half * &Real::Float(3.0), Real::Float(1.5));
+    assert_eq!(one.checked_div(&Real::Integer(3)), Some(Real::Ration(1, 3)));
+    assert_eq!(one.checked_div(&Real::Integer(0)), None);
+    assert_eq!(one.checked_div(&Real::Float(0.0)), Some(Real::PosInf));
+
+    // exact results leave i64 when they need to, and come back
+    let big = &Real::Integer(i64::MAX) + &one;
+    assert_eq!(big, Real::BigInteger(Box::new(BigInt::from(i64::MAX) + 1)));
+    assert_eq!(&big - &one, Real::Integer(i64::MAX));
+    assert_eq!(&Real::Integer(i64::MIN) * &Real::Integer(-1), Real::integer(-BigInt::from(i64::MIN)));
+
+    let exact = Complex::new(Real::Integer(1), Real::Integer(2));
+    let inexact = Complex::new(Real::Float(0.5), Real::Integer(0));
+    assert_eq!(&exact + &inexact, Complex::Complex(Real::Float(1.5), Real::Integer(2)));
+    assert!(!(&exact * &inexact).is_exact());
+    assert_eq!(&exact - &exact, Complex::Real(Real::Integer(0)));
+}
+
+#[test]
+fn rounding() {
+    // exact halves round to even
+    assert_eq!(ratio(5, 2).round(), Real::Integer(2));
+    assert_eq!(ratio(7, 2).round(), Real::Integer(4));
+    assert_eq!(ratio(-5, 2).round(), Real::Integer(-2));
+    assert_eq!(ratio(-7, 3).round(), Real::Integer(-2));
+    assert_eq!(Real::Float(2.5).round(), Real::Float(2.0));
+    assert_eq!(Real::Float(-3.5).round(), Real::Float(-4.0));
+
+    assert_eq!(ratio(-7, 2).floor(), Real::Integer(-4));
+    assert_eq!(ratio(-7, 2).ceiling(), Real::Integer(-3));
+    assert_eq!(ratio(-7, 2).truncate(), Real::Integer(-3));
+    assert_eq!(Real::Float(-3.5).floor(), Real::Float(-4.0));
+    assert_eq!(Real::Float(-3.5).truncate(), Real::Float(-3.0));
+    assert_eq!(Real::PosInf.floor(), Real::PosInf);
+    assert_eq!(Real::Integer(7).round(), Real::Integer(7));
+}
+
+#[test]
+fn integer_division() {
+    let int = Real::Integer;
+    assert_eq!(int(7).floor_div(&int(2)), Some((int(3), int(1))));
+    assert_eq!(int(-7).floor_div(&int(2)), Some((int(-4), int(1))));
+    assert_eq!(int(7).floor_div(&int(-2)), Some((int(-4), int(-1))));
+    assert_eq!(int(-7).truncate_div(&int(2)), Some((int(-3), int(-1))));
+    assert_eq!(int(7).truncate_div(&int(-2)), Some((int(-3), int(1))));
+    assert_eq!(int(7).floor_div(&int(0)), None);
+    assert_eq!(int(7).truncate_div(&Real::Float(0.0)), None);
+
+    // an inexact operand makes both results inexact
+    assert_eq!(Real::Float(-7.0).floor_div(&int(2)), Some((Real::Float(-4.0), Real::Float(1.0))));
+    assert_eq!(int(-7).truncate_div(&Real::Float(2.0)), Some((Real::Float(-3.0), Real::Float(-1.0))));
+
+    let big = Real::integer(BigInt::from(i64::MAX) * 4 + 3);
+    assert_eq!(big.floor_div(&int(4)), Some((int(i64::MAX), int(3))));
+    assert_eq!(int(i64::MIN).truncate_div(&int(-1)), Some((Real::integer(-BigInt::from(i64::MIN)), int(0))));
+}
+
+#[test]
+fn exactness_conversion() {
+    assert_eq!(Real::Float(0.5).to_exact(), Some(ratio(1, 2)));
+    assert_eq!(Real::Float(-2.0).to_exact(), Some(Real::Integer(-2)));
+    // the exact value of the nearest flonum, not the shortest decimal
+    assert_eq!(Real::Float(0.1).to_exact(), Some(Real::Ration(3602879701896397, 36028797018963968)));
+    assert_eq!(Real::Float(1e20).to_exact(), Some(Real::integer(BigInt::from(10).pow(20))));
+    assert_eq!(Real::PosInf.to_exact(), None);
+    assert_eq!(Real::PosNan.to_exact(), None);
+
+    assert_eq!(ratio(1, 4).to_inexact(), Real::Float(0.25));
+    assert_eq!(Real::integer(BigInt::from(10).pow(400)).to_inexact(), Real::PosInf);
+    assert_eq!(Real::Float(1.5).to_inexact(), Real::Float(1.5));
+
+    let complex = Complex::new(Real::Float(0.5), Real::Float(-1.0));
+    assert_eq!(complex.to_exact(), Some(Complex::Complex(ratio(1, 2), Real::Integer(-1))));
+    assert_eq!(complex.to_exact().unwrap().to_inexact(), complex);
+    assert_eq!(Complex::new(Real::Integer(1), Real::PosInf).to_exact(), None);
+}
@@ -1,17 +1,8 @@
-
-
-
 // 6.4 Pairs and lists
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum GenericPair<T> {
     Some(T, T),
+    #[default]
     Empty,
 }
-
-impl<T> Default for GenericPair<T> {
-    fn default() -> Self {
-        GenericPair::Empty
-    }
-}
-
@@ -0,0 +1,105 @@
+use std::fmt;
+use std::path::Path;
+
+use super::{Location, Span};
+
+// Which source text a location is in. The default id is for text that is not
+// in a source map, such as the input of `Interpreter::eval_str`.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+pub struct FileId(u32);
+
+// The source texts of a program, each under the path it was loaded from.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: String,
+    pub text: String,
+}
+
+// A location as people count it: lines and columns from one.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct SourcePosition<'a> {
+    pub path: &'a str,
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for SourcePosition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.col)
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: &str, text: &str) -> FileId {
+        self.files.push(SourceFile{path: path.to_string(), text: text.to_string()});
+        // ids count from one, leaving the default id to text outside the map
+        FileId(self.files.len() as u32)
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> std::io::Result<FileId> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Ok(self.add(&path.to_string_lossy(), &text))
+    }
+
+    pub fn get(&self, file: FileId) -> Option<&SourceFile> {
+        let index = (file.0 as usize).checked_sub(1)?;
+        self.files.get(index)
+    }
+
+    pub fn resolve(&self, location: Location) -> Option<SourcePosition<'_>> {
+        let file = self.get(location.file)?;
+        Some(SourcePosition{path: &file.path, line: location.row + 1, col: location.col + 1})
+    }
+
+    // The text of the line `location` is on, without its line ending.
+    pub fn line(&self, location: Location) -> Option<&str> {
+        let text = &self.get(location.file)?.text;
+        let offset = location.offset.min(text.len());
+        let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+        Some(text[start..end].trim_end_matches('\r'))
+    }
+
+    // The text `span` covers; `None` when it is not a span of a file here.
+    pub fn slice(&self, span: Span) -> Option<&str> {
+        if span.start.file != span.end.file {
+            return None;
+        }
+        self.get(span.start.file)?.text.get(span.range())
+    }
+}
+
+#[test]
+fn resolve() {
+    use crate::{Lexer, LexerOptions, Processor};
+
+    let mut sources = SourceMap::new();
+    let first = sources.add("first.scm", "(define x 1)\n");
+    let second = sources.add("lib/second.scm", "; λ\r\n(display\n  \"two\")\n");
+    assert_ne!(first, second);
+    assert_ne!(first, FileId::default());
+    assert_eq!(sources.get(second).unwrap().path, "lib/second.scm");
+    assert!(sources.get(FileId::default()).is_none());
+
+    let options = LexerOptions{file: second, ..LexerOptions::default()};
+    let text = &sources.get(second).unwrap().text;
+    let datum = Processor::from(Lexer::with_options(text.chars(), options)).next().unwrap();
+    let start = sources.resolve(datum.location()).unwrap();
+    assert_eq!(start.to_string(), "lib/second.scm:2:1");
+    assert_eq!(sources.line(datum.location()), Some("(display"));
+    assert_eq!(sources.line(datum.span.end), Some("  \"two\")"));
+    assert_eq!(sources.slice(datum.span), Some("(display\n  \"two\")"));
+
+    assert!(sources.resolve(Location::default()).is_none());
+    assert_eq!(sources.resolve(Location{file: first, ..Location::default()}).unwrap().to_string(), "first.scm:1:1");
+}
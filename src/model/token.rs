@@ -1,3 +1,10 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed as _, ToPrimitive, Zero};
+
 use super::ToLocated;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -15,6 +22,9 @@ pub enum Token {
     Unquote,          // ,
     UnquoteSplicing,  // ,@
     Period,           // .
+    DatumComment,     // #;
+    Label(usize),     // #n=
+    LabelReference(usize), // #n#
 }
 
 impl ToLocated for Token {}
@@ -42,19 +52,207 @@ pub enum Real {
     NegNan,
     Integer(i64),
     Ration(i64, u64),
+    // exact numbers too large for the cases above; `Real::integer` and
+    // `Real::rational` only build them when needed
+    BigInteger(Box<BigInt>),
+    BigRation(Box<BigRational>),
     Float(f64),
 }
 
 impl Real {
+    // The normalized form of an exact integer.
+    pub fn integer(i: BigInt) -> Self {
+        match i64::try_from(&i) {
+            Ok(i) => Real::Integer(i),
+            Err(_) => Real::BigInteger(Box::new(i)),
+        }
+    }
+
+    // The normalized form of an exact rational: reduced, and an integer when
+    // the denominator is one.
+    pub fn rational(r: BigRational) -> Self {
+        if r.is_integer() {
+            return Real::integer(r.to_integer());
+        }
+        match (i64::try_from(r.numer()), u64::try_from(r.denom())) {
+            (Ok(a), Ok(b)) => Real::Ration(a, b),
+            _ => Real::BigRation(Box::new(r)),
+        }
+    }
+
+    // `None` for a zero denominator.
+    pub fn fraction(numerator: BigInt, denominator: BigInt) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+        Some(Real::rational(BigRational::new(numerator, denominator)))
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Real::Integer(_) | Real::Ration(..) | Real::BigInteger(_) | Real::BigRation(_))
+    }
+
+    // The value of an exact number.
+    pub fn to_rational(&self) -> Option<BigRational> {
+        Some(match self {
+            Real::Integer(i) => BigRational::from_integer(BigInt::from(*i)),
+            Real::Ration(a, b) => BigRational::new_raw(BigInt::from(*a), BigInt::from(*b)),
+            Real::BigInteger(i) => BigRational::from_integer((**i).clone()),
+            Real::BigRation(r) => (**r).clone(),
+            _ => return None,
+        })
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Real::PosInf => f64::INFINITY,
+            Real::NegInf => f64::NEG_INFINITY,
+            Real::PosNan => f64::NAN,
+            Real::NegNan => -f64::NAN,
+            Real::Integer(i) => *i as f64,
+            Real::Ration(a, b) => *a as f64 / *b as f64,
+            Real::BigInteger(i) => i.to_f64().unwrap_or(f64::NAN),
+            Real::BigRation(r) => r.to_f64().unwrap_or(f64::NAN),
+            Real::Float(f) => *f,
+        }
+    }
+
     pub fn reverse(self) -> Self {
         match self {
             Real::PosInf => Real::NegInf,
             Real::NegInf => Real::PosInf,
             Real::PosNan => Real::NegNan,
             Real::NegNan => Real::PosNan,
-            Real::Integer(i) => Real::Integer(-i),
-            Real::Ration(a, b) => Real::Ration(-a, b),
             Real::Float(f) => Real::Float(-f),
+            exact => Real::rational(-exact.to_rational().unwrap()),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Real::NegInf => true,
+            Real::Integer(i) => *i < 0,
+            Real::Ration(a, _) => *a < 0,
+            Real::BigInteger(i) => i.is_negative(),
+            Real::BigRation(r) => r.is_negative(),
+            Real::Float(f) => *f < 0.0,
+            _ => false,
+        }
+    }
+}
+
+// External representations, as read back by the lexer.
+
+impl fmt::Display for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Primitive::Boolean(true) => write!(f, "#t"),
+            Primitive::Boolean(false) => write!(f, "#f"),
+            Primitive::Complex(c) => write!(f, "{}", c),
+            Primitive::Character(c) => write_character(f, *c),
+            Primitive::String(s) => write_string(f, s),
+        }
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Complex::Real(real) => write!(f, "{}", real),
+            Complex::Complex(real, imaginary_part) => write!(f, "{}{}", real, imaginary(&imaginary_part.to_string())),
+            Complex::Imaginary(imaginary_part) => write!(f, "{}", imaginary(&imaginary_part.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Real {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Real::PosInf => write!(f, "+inf.0"),
+            Real::NegInf => write!(f, "-inf.0"),
+            Real::PosNan => write!(f, "+nan.0"),
+            Real::NegNan => write!(f, "-nan.0"),
+            Real::Integer(i) => write!(f, "{}", i),
+            Real::Ration(a, b) => write!(f, "{}/{}", a, b),
+            Real::BigInteger(i) => write!(f, "{}", i),
+            Real::BigRation(r) => write!(f, "{}/{}", r.numer(), r.denom()),
+            Real::Float(x) if x.is_nan() => write!(f, "{}nan.0", if x.is_sign_negative() {"-"} else {"+"}),
+            Real::Float(x) if x.is_infinite() => write!(f, "{}inf.0", if *x < 0.0 {"-"} else {"+"}),
+            // `Debug` keeps a fractional part or exponent, so the float
+            // stays inexact when read back
+            Real::Float(x) => write!(f, "{:?}", x),
+        }
+    }
+}
+
+// The imaginary part of a complex number, with an explicit sign and `i`; a
+// unit part is written as just its sign.
+fn imaginary(text: &str) -> String {
+    match text {
+        "1" => "+i".to_string(),
+        "-1" => "-i".to_string(),
+        _ if text.starts_with('+') || text.starts_with('-') => format!("{}i", text),
+        _ => format!("+{}i", text),
+    }
+}
+
+impl Complex {
+    // 6.2.7 number->string: `None` for an inexact number in a radix other
+    // than 10.
+    pub fn to_string_radix(&self, radix: u32) -> Option<String> {
+        if radix == 10 {
+            return Some(self.to_string());
+        }
+        let part = |real: &Real| {
+            let r = real.to_rational()?;
+            Some(match r.is_integer() {
+                true => r.numer().to_str_radix(radix),
+                false => format!("{}/{}", r.numer().to_str_radix(radix), r.denom().to_str_radix(radix)),
+            })
+        };
+        Some(match self {
+            Complex::Real(real) => part(real)?,
+            Complex::Complex(real, imaginary_part) => part(real)? + &imaginary(&part(imaginary_part)?),
+            Complex::Imaginary(imaginary_part) => imaginary(&part(imaginary_part)?),
+        })
+    }
+}
+
+// 6.6 the character names of R7RS
+pub(crate) const CHARACTER_NAMES: [(char, &str); 9] = [
+    ('\u{7}', "alarm"),
+    ('\u{8}', "backspace"),
+    ('\u{7f}', "delete"),
+    ('\u{1b}', "escape"),
+    ('\n', "newline"),
+    ('\0', "null"),
+    ('\r', "return"),
+    (' ', "space"),
+    ('\t', "tab"),
+];
+
+pub(crate) fn write_character(f: &mut fmt::Formatter<'_>, c: char) -> fmt::Result {
+    match CHARACTER_NAMES.iter().find(|(named, _)| *named == c) {
+        Some((_, name)) => write!(f, "#\\{}", name),
+        None if c.is_control() => write!(f, "#\\x{:x}", c as u32),
+        None => write!(f, "#\\{}", c),
+    }
+}
+
+pub(crate) fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\u{7}' => write!(f, "\\a")?,
+            '\u{8}' => write!(f, "\\b")?,
+            '\t' => write!(f, "\\t")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            c if c.is_control() => write!(f, "\\x{:x};", c as u32)?,
+            c => write!(f, "{}", c)?,
         }
     }
-}
\ No newline at end of file
+    write!(f, "\"")
+}
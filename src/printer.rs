@@ -0,0 +1,531 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use super::{Value, Datum, DatumPair, write_character, write_string, write_symbol, abbreviation};
+
+// 6.13.3 Output: how `display`, `write`, `write-shared` and `write-simple`
+// print values.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Style {
+    // strings and characters as their contents; labels only for cycles
+    Display,
+    // the external representation; labels only for cycles
+    Write,
+    // the external representation; labels for every shared pair or vector
+    WriteShared,
+    // the external representation without labels, so cycles never end
+    WriteSimple,
+}
+
+// A value prepared for printing with `{}`: the pairs and vectors that need a
+// datum label are found up front.
+pub struct Printer<'a> {
+    value: &'a Value,
+    style: Style,
+    // labelled objects by address, with their number once printed
+    labels: RefCell<HashMap<usize, Option<usize>>>,
+    next_label: Cell<usize>,
+}
+
+impl<'a> Printer<'a> {
+    pub fn new(value: &'a Value, style: Style) -> Self {
+        let mut labels = HashMap::new();
+        if style != Style::WriteSimple {
+            let mut scan = Scan {
+                shared: style == Style::WriteShared,
+                labels: &mut labels,
+                seen: HashSet::new(),
+                path: HashSet::new(),
+            };
+            scan.value(value);
+        }
+        Self {
+            value,
+            style,
+            labels: RefCell::new(labels),
+            next_label: Cell::new(0),
+        }
+    }
+
+    fn is_labelled(&self, address: usize) -> bool {
+        self.labels.borrow().contains_key(&address)
+    }
+
+    // Writes the label of a labelled object: `#n#` when it has been printed
+    // already, in which case this returns true, and `#n=` otherwise.
+    fn label(&self, f: &mut fmt::Formatter<'_>, address: usize) -> Result<bool, fmt::Error> {
+        let mut labels = self.labels.borrow_mut();
+        match labels.get_mut(&address) {
+            None => Ok(false),
+            Some(Some(n)) => write!(f, "#{}#", n).map(|_| true),
+            Some(label) => {
+                let n = self.next_label.get();
+                self.next_label.set(n + 1);
+                *label = Some(n);
+                write!(f, "#{}=", n).map(|_| false)
+            },
+        }
+    }
+
+    fn print(&self, f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+        let display = self.style == Style::Display;
+        match value {
+            Value::Boolean(true) => write!(f, "#t"),
+            Value::Boolean(false) => write!(f, "#f"),
+            Value::Number(c) => write!(f, "{}", c),
+            Value::Character(c) if display => write!(f, "{}", c),
+            Value::Character(c) => write_character(f, *c),
+            Value::String(s) if display => write!(f, "{}", s.borrow()),
+            Value::String(s) => write_string(f, &s.borrow()),
+            Value::Symbol(s) if display => write!(f, "{}", s),
+            Value::Symbol(s) => write_symbol(f, s),
+            Value::ByteVector(bytes) => {
+                write!(f, "#u8(")?;
+                for (i, b) in bytes.borrow().iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 {""} else {" "}, b)?;
+                }
+                write!(f, ")")
+            },
+            Value::Null => write!(f, "()"),
+            Value::Pair(pair) => {
+                if self.label(f, Rc::as_ptr(pair) as usize)? {
+                    return Ok(());
+                }
+                write!(f, "(")?;
+                self.print(f, &pair.car())?;
+                let mut rest = pair.cdr();
+                loop {
+                    rest = match rest {
+                        Value::Pair(pair) if !self.is_labelled(Rc::as_ptr(&pair) as usize) => {
+                            write!(f, " ")?;
+                            self.print(f, &pair.car())?;
+                            pair.cdr()
+                        },
+                        Value::Null => break,
+                        tail => {
+                            write!(f, " . ")?;
+                            self.print(f, &tail)?;
+                            break;
+                        },
+                    };
+                }
+                write!(f, ")")
+            },
+            Value::Vector(values) => {
+                if self.label(f, Rc::as_ptr(values) as usize)? {
+                    return Ok(());
+                }
+                write!(f, "#(")?;
+                for (i, v) in values.borrow().iter().enumerate() {
+                    write!(f, "{}", if i == 0 {""} else {" "})?;
+                    self.print(f, v)?;
+                }
+                write!(f, ")")
+            },
+            Value::Procedure(procedure) => match procedure.name() {
+                Some(name) => write!(f, "#<procedure {}>", name),
+                None => write!(f, "#<procedure>"),
+            },
+            Value::Port(_) => write!(f, "#<port>"),
+            Value::Record(record) => write!(f, "#<{}>", record.record_type.name),
+            Value::Error(error) => {
+                write!(f, "#<error ")?;
+                write_string(f, &error.message)?;
+                for irritant in error.irritants.iter() {
+                    write!(f, " ")?;
+                    self.print(f, irritant)?;
+                }
+                write!(f, ">")
+            },
+            Value::Values(values) => {
+                write!(f, "#<values")?;
+                for v in values.iter() {
+                    write!(f, " ")?;
+                    self.print(f, v)?;
+                }
+                write!(f, ">")
+            },
+            Value::Unspecified => write!(f, "#<unspecified>"),
+            Value::Eof => write!(f, "#<eof>"),
+        }
+    }
+}
+
+impl fmt::Display for Printer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.print(f, self.value)
+    }
+}
+
+// The addresses of the pairs and vectors on a cycle, which need a datum label
+// to be written out finitely.
+pub(crate) fn cycles(value: &Value) -> HashSet<usize> {
+    let mut labels = HashMap::new();
+    let mut scan = Scan {
+        shared: false,
+        labels: &mut labels,
+        seen: HashSet::new(),
+        path: HashSet::new(),
+    };
+    scan.value(value);
+    labels.into_keys().collect()
+}
+
+// Finds the objects to label by a depth-first walk: those reached again while
+// still being walked (cycles), or when `shared`, reached more than once.
+struct Scan<'a> {
+    shared: bool,
+    labels: &'a mut HashMap<usize, Option<usize>>,
+    seen: HashSet<usize>,
+    path: HashSet<usize>,
+}
+
+impl Scan<'_> {
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Pair(_) => {
+                let mut spine = vec![];
+                let mut current = value.clone();
+                loop {
+                    let pair = match current {
+                        Value::Pair(pair) => pair,
+                        tail => {
+                            self.value(&tail);
+                            break;
+                        },
+                    };
+                    let address = Rc::as_ptr(&pair) as usize;
+                    if !self.enter(address) {
+                        break;
+                    }
+                    spine.push(address);
+                    self.value(&pair.car());
+                    current = pair.cdr();
+                }
+                for address in spine {
+                    self.path.remove(&address);
+                }
+            },
+            Value::Vector(values) => {
+                let address = Rc::as_ptr(values) as usize;
+                if self.enter(address) {
+                    for v in values.borrow().iter() {
+                        self.value(v);
+                    }
+                    self.path.remove(&address);
+                }
+            },
+            Value::Error(error) => error.irritants.iter().for_each(|v| self.value(v)),
+            Value::Values(values) => values.iter().for_each(|v| self.value(v)),
+            _ => (),
+        }
+    }
+
+    // Returns whether the object is new and should be walked.
+    fn enter(&mut self, address: usize) -> bool {
+        if self.path.contains(&address) || (self.shared && self.seen.contains(&address)) {
+            self.labels.insert(address, None);
+        }
+        let new = self.seen.insert(address);
+        if new {
+            self.path.insert(address);
+        }
+        new
+    }
+}
+
+// A datum laid out over several lines so that, as far as possible, no line is
+// wider than `width`. Forms that do not fit are broken in the conventional
+// way: the bodies of `define`, `lambda`, `let` and friends are indented by
+// two columns, the clauses of `cond` line up, and the operands of any other
+// form line up under the first one when it fits.
+pub struct PrettyPrinter<'a> {
+    datum: &'a Datum,
+    width: usize,
+}
+
+impl<'a> PrettyPrinter<'a> {
+    pub fn new(datum: &'a Datum, width: usize) -> Self {
+        Self { datum, width }
+    }
+
+    fn print(&self, out: &mut String, datum: &Datum) {
+        let start = column(out);
+        if let Some(flat) = flat(datum, self.width.saturating_sub(start)) {
+            return out.push_str(&flat);
+        }
+        match datum {
+            Datum::Pair(pair) => match pair.as_ref() {
+                DatumPair::Some(car, cdr) => match abbreviation(car, cdr) {
+                    Some((prefix, datum)) => {
+                        out.push_str(prefix);
+                        self.print(out, datum);
+                    },
+                    None => {
+                        let (elements, tail) = list_elements(datum);
+                        out.push('(');
+                        self.print_list(out, &elements, tail, start);
+                    },
+                },
+                DatumPair::Empty => out.push_str("()"),
+            },
+            Datum::Vector(data) if !data.is_empty() => {
+                out.push_str("#(");
+                self.print(out, &data[0].data);
+                let rest = data[1..].iter().map(|d| &d.data).collect::<Vec<_>>();
+                self.print_lines(out, &rest, None, start + 2);
+            },
+            Datum::Labelled(n, datum) => {
+                out.push_str(&format!("#{}=", n));
+                self.print(out, &datum.data);
+            },
+            _ => out.push_str(&datum.to_string()),
+        }
+    }
+
+    // Writes the elements of a list after its opening parenthesis, which is
+    // at column `start`.
+    fn print_list(&self, out: &mut String, elements: &[&Datum], tail: Option<&Datum>, start: usize) {
+        let keyword = match elements[0] {
+            Datum::Symbol(s) => Some(s.as_str()),
+            _ => None,
+        };
+        let operand = match keyword {
+            Some(keyword) => start + keyword.chars().count() + 2,
+            None => start + 1,
+        };
+        let operand_fits = elements.len() > 1 && flat(elements[1], self.width.saturating_sub(operand)).is_some();
+        let (first_line, indent) = match (keyword, body_position(elements)) {
+            (Some(_), Some(n)) => (n.min(elements.len()), start + 2),
+            (Some(_), None) if operand_fits => (2, operand),
+            _ => (1, start + 1),
+        };
+        self.print(out, elements[0]);
+        for element in elements[1..first_line].iter() {
+            out.push(' ');
+            self.print(out, element);
+        }
+        self.print_lines(out, &elements[first_line..], tail, indent);
+    }
+
+    // Writes each of `data` on a line of its own at column `indent`, then the
+    // closing parenthesis.
+    fn print_lines(&self, out: &mut String, data: &[&Datum], tail: Option<&Datum>, indent: usize) {
+        for datum in data.iter() {
+            out.push('\n');
+            out.push_str(&" ".repeat(indent));
+            self.print(out, datum);
+        }
+        if let Some(tail) = tail {
+            out.push('\n');
+            out.push_str(&" ".repeat(indent));
+            out.push_str(". ");
+            self.print(out, tail);
+        }
+        out.push(')');
+    }
+}
+
+impl fmt::Display for PrettyPrinter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.print(&mut out, self.datum);
+        write!(f, "{}", out)
+    }
+}
+
+// The number of elements, keyword included, that stay on the first line of a
+// form with a body.
+fn body_position(elements: &[&Datum]) -> Option<usize> {
+    let keyword = match elements[0] {
+        Datum::Symbol(s) => s.as_str(),
+        _ => return None,
+    };
+    Some(match keyword {
+        "define" | "define-values" | "define-syntax" | "lambda" | "let*" | "letrec" | "letrec*"
+            | "let-values" | "let*-values" | "let-syntax" | "letrec-syntax" | "when" | "unless"
+            | "case" | "guard" | "parameterize" | "syntax-rules" | "case-lambda" => 2,
+        "let" => match elements.get(1) {
+            Some(Datum::Symbol(_)) => 3,
+            _ => 2,
+        },
+        "do" | "define-record-type" => 3,
+        "begin" => 1,
+        _ => return None,
+    })
+}
+
+// The elements of a list, and the tail of an improper one.
+fn list_elements(datum: &Datum) -> (Vec<&Datum>, Option<&Datum>) {
+    let mut elements = vec![];
+    let mut rest = datum;
+    loop {
+        match rest {
+            Datum::Pair(pair) => match pair.as_ref() {
+                DatumPair::Some(car, cdr) => {
+                    elements.push(&car.data);
+                    rest = &cdr.data;
+                },
+                DatumPair::Empty => return (elements, None),
+            },
+            tail => return (elements, Some(tail)),
+        }
+    }
+}
+
+// `datum` on one line, if it takes at most `room` columns. Writing stops as
+// soon as it runs out of room, so that measuring every level of a large
+// datum costs no more than the width at each.
+fn flat(datum: &Datum, room: usize) -> Option<String> {
+    let mut out = Bounded{text: String::new(), room};
+    fmt::Write::write_fmt(&mut out, format_args!("{}", datum)).ok()?;
+    Some(out.text)
+}
+
+struct Bounded {
+    text: String,
+    room: usize,
+}
+
+impl fmt::Write for Bounded {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let length = s.chars().count();
+        if length > self.room {
+            return Err(fmt::Error);
+        }
+        self.room -= length;
+        self.text.push_str(s);
+        Ok(())
+    }
+}
+
+fn column(out: &str) -> usize {
+    out[out.rfind('\n').map_or(0, |i| i + 1)..].chars().count()
+}
+
+#[cfg(test)]
+fn read_all(text: &str) -> Vec<super::Datum> {
+    use super::{Lexer, Processor};
+
+    Processor::from(Lexer::new(text.chars())).map(|datum| datum.data.unwrap()).collect()
+}
+
+#[test]
+fn datum_round_trip() {
+    let text = r#"
+        (a b . c) (1 (2 3) ()) #(1 #(x) "s") #u8(0 255)
+        'q `(a ,b ,@c) (quote) (quote a b)
+        "tab\tquote\" back\\ bell\a" #\a #\( #\λ #\space #\x1 "\x1;\x3bb;"
+        -12 3/4 -1.5 1e21 1e-7 +inf.0 -nan.0 1+2i -2i
+        |two words| |(| |1x| |+i| |a\|b| |\x3bb;\t| + - ... ->x
+        #0=(a b . #0#) (#1=(x) #1#) #2=#(1 '#2#)
+        #t #f
+    "#;
+    let data = read_all(text);
+    let printed = data.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(" ");
+    assert_eq!(printed, concat!(
+        r#"(a b . c) (1 (2 3) ()) #(1 #(x) "s") #u8(0 255) "#,
+        r#"'q `(a ,b ,@c) (quote) (quote a b) "#,
+        r#""tab\tquote\" back\\ bell\a" #\a #\( #\λ #\space #\x1 "\x1;λ" "#,
+        r#"-12 3/4 -1.5 1e21 1e-7 +inf.0 -nan.0 1+2i -2i "#,
+        r#"|two words| |(| |1x| |+i| |a\|b| |λ\x9;| + - ... ->x "#,
+        "#0=(a b . #0#) (#1=(x) #1#) #2=#(1 '#2#) ",
+        "#t #f",
+    ));
+
+    let reread = read_all(&printed);
+    assert_eq!(reread.len(), data.len());
+    for (original, reread) in data.iter().zip(reread.iter()) {
+        assert_eq!(Value::from(original).to_string(), Value::from(reread).to_string());
+        assert_eq!(original.to_string(), reread.to_string());
+    }
+
+    assert_eq!(Printer::new(&Value::Character(' '), Style::Write).to_string(), r"#\space");
+    assert_eq!(Printer::new(&Value::Character('\n'), Style::Write).to_string(), r"#\newline");
+    assert_eq!(Printer::new(&Value::Symbol("a|b\\".to_string()), Style::Write).to_string(), r"|a\|b\\|");
+    assert_eq!(Printer::new(&Value::string("\u{1}"), Style::Write).to_string(), r#""\x1;""#);
+}
+
+#[test]
+fn labels() {
+    let mut interpreter = super::Interpreter::new();
+    let print = |interpreter: &mut super::Interpreter, text: &str, style: Style| {
+        Printer::new(&interpreter.eval_str(text).unwrap(), style).to_string()
+    };
+    interpreter.eval_str("
+        (define cycle (list 1 2))
+        (set-cdr! (cdr cycle) cycle)
+        (define shared (list 'x))
+        (define vector (vector 1 2))
+        (vector-set! vector 1 vector)
+    ").unwrap();
+
+    assert_eq!(print(&mut interpreter, "cycle", Style::Write), "#0=(1 2 . #0#)");
+    assert_eq!(print(&mut interpreter, "(list cycle cycle)", Style::Write), "(#0=(1 2 . #0#) #0#)");
+    assert_eq!(print(&mut interpreter, "vector", Style::Display), "#0=#(1 #0#)");
+    assert_eq!(print(&mut interpreter, "(list shared shared)", Style::Write), "((x) (x))");
+    assert_eq!(print(&mut interpreter, "(list shared shared)", Style::WriteShared), "(#0=(x) #0#)");
+    assert_eq!(print(&mut interpreter, "(cons shared shared)", Style::WriteShared), "(#0=(x) . #0#)");
+    assert_eq!(print(&mut interpreter, "(list shared shared)", Style::WriteSimple), "((x) (x))");
+    assert_eq!(print(&mut interpreter, "(list \"a\" #\\b 'c)", Style::Display), "(a b c)");
+    assert_eq!(print(&mut interpreter, "(list \"a\" #\\b 'c)", Style::Write), "(\"a\" #\\b c)");
+
+    interpreter.eval_str("
+        (define out (open-output-string))
+        (write '|a b| out)
+        (display \" \" out)
+        (write-shared (list shared shared) out)
+        (newline out)
+    ").unwrap();
+    assert_eq!(interpreter.eval_str("(get-output-string out)").unwrap(), Value::string("|a b| (#0=(x) #0#)\n"));
+}
+
+#[test]
+fn pretty() {
+    let pretty = |text: &str, width: usize| {
+        let data = read_all(text);
+        let printed = PrettyPrinter::new(&data[0], width).to_string();
+        assert_eq!(read_all(&printed)[0].to_string(), data[0].to_string());
+        printed
+    };
+
+    assert_eq!(pretty("(define (f x) (+ x 1))", 80), "(define (f x) (+ x 1))");
+    assert_eq!(pretty("(define (f x) (let loop ((i 0) (acc '())) (if (< i x) (loop (+ i 1) (cons i acc)) acc)))", 40), "\
+(define (f x)
+  (let loop ((i 0) (acc '()))
+    (if (< i x)
+        (loop (+ i 1) (cons i acc))
+        acc)))");
+    assert_eq!(pretty("(lambda (x) (cond ((number? x) (* x x)) ((string? x) (string-length x)) (else #f)))", 30), "\
+(lambda (x)
+  (cond ((number? x) (* x x))
+        ((string? x)
+         (string-length x))
+        (else #f)))");
+    assert_eq!(pretty("((alpha . 1) (beta . 2) (gamma 3 4 . 5) #(delta epsilon zeta))", 15), "\
+((alpha . 1)
+ (beta . 2)
+ (gamma 3
+        4
+        . 5)
+ #(delta
+   epsilon
+   zeta))");
+    assert_eq!(pretty("'(a-long-symbol another-long-symbol)", 20), "\
+'(a-long-symbol
+  another-long-symbol)");
+    assert_eq!(pretty("(f (g a-long-symbol another-long-symbol))", 20), "\
+(f
+ (g a-long-symbol
+    another-long-symbol))");
+
+    // only as much of each level is measured as fits on a line
+    let deep = format!("{}x{}", "(f ".repeat(50), ")".repeat(50));
+    let printed = pretty(&deep, 40);
+    let lines: Vec<_> = printed.lines().map(str::trim_start).collect();
+    assert_eq!(lines.len(), 51);
+    assert!(lines[..50].iter().all(|line| *line == "(f"));
+    assert_eq!(lines[50], format!("x{}", ")".repeat(50)));
+}
@@ -1,4 +1,6 @@
-use super::{Token, LexerError, Located, ProcessorError, Datum, ToLocated, DatumPair, Primitive, Complex, Real};
+use super::{Token, LexerError, Located, Span, ProcessorError, Datum, ToLocated, DatumPair, Primitive, Complex, Real};
+
+use std::collections::HashSet;
 
 use peekmore::{PeekMore, PeekMoreIterator};
 
@@ -8,15 +10,22 @@ type TResult = Located<std::result::Result<Token, LexerError>>;
 
 pub struct Processor<TokenIter: Iterator<Item = TResult>> {
     tokens: PeekMoreIterator<TokenIter>,
+    // the datum labels defined so far in the current top-level datum
+    labels: HashSet<usize>,
+    // those among them whose datum is still being read
+    open: Vec<usize>,
 }
 
 impl<TokenIter: Iterator<Item = TResult>> Iterator for Processor<TokenIter> {
     type Item = Located<std::result::Result<Datum, ProcessorError>>;
     fn next(&mut self) -> Option<Self::Item> {
+        // 2.4 the scope of a label is the outermost datum it appears in
+        self.labels.clear();
+        self.open.clear();
         match self.get_next_datum() {
-            Ok(Some(Located{data: datum, location})) => Some(Ok(datum).with_location(location)),
+            Ok(Some(Located{data: datum, span})) => Some(Ok(datum).with_span(span)),
             Ok(None) => None,
-            Err(Located{data: e, location}) => Some(Err(e).with_location(location)),
+            Err(Located{data: e, span}) => Some(Err(e).with_span(span)),
         }
     }
 }
@@ -25,6 +34,8 @@ impl<TokenIter: Iterator<Item = TResult>> Processor<TokenIter> {
     pub fn from(tokens: TokenIter) -> Processor<TokenIter> {
         Self {
             tokens: tokens.peekmore(),
+            labels: HashSet::new(),
+            open: vec![],
         }
     }
 
@@ -33,7 +44,7 @@ impl<TokenIter: Iterator<Item = TResult>> Processor<TokenIter> {
     }
 
     fn peek(&mut self) -> Option<TResult> {
-        let res = self.tokens.peek().map(|t| t.clone());
+        let res = self.tokens.peek().cloned();
         self.tokens.advance_cursor();
         res
     }
@@ -47,116 +58,171 @@ impl<TokenIter: Iterator<Item = TResult>> Processor<TokenIter> {
     }
 
     pub fn get_next_datum(&mut self) -> Result<Option<Located<Datum>>> {
-        let (token, location) = match self.peek() {
+        self.skip_datum_comments()?;
+        let (token, span) = match self.peek() {
             None => return Ok(None),
-            Some(Located{data: Err(e), location}) => return Err(ProcessorError::LexerError(e).with_location(location)),
-            Some(Located{data: Ok(token), location}) => (token, location),
+            Some(Located{data: Err(e), span}) => return Err(ProcessorError::LexerError(e).with_span(span)),
+            Some(Located{data: Ok(token), span}) => (token, span),
         };
+        let location = span.start;
         self.reset();
 
-        return Ok(Some(match token {
+        Ok(Some(match token {
             // simple datum
-            Token::Primitive(p) => {self.advance(); Datum::Primitive(p)},
-            Token::ByteVecConsIntro => self.get_bytevector()?.data,
-            Token::Identifier(i) => {self.advance(); Datum::Symbol(i)},
+            Token::Primitive(p) => {self.advance(); Datum::Primitive(p).with_span(span)},
+            Token::ByteVecConsIntro => self.get_bytevector()?,
+            Token::Identifier(i) => {self.advance(); Datum::Symbol(i).with_span(span)},
 
             // compound datum
-            Token::LeftParen => self.get_pair()?.data,
-            Token::VecConsIntro => self.get_vector()?.data,
+            Token::LeftParen => self.get_pair()?,
+            Token::VecConsIntro => self.get_vector()?,
             
             // abbreviation
-            Token::Quote => self.get_transform(token, "quote")?.data,
-            Token::Quasiquote => self.get_transform(token, "quasiquote")?.data,
-            Token::Unquote => self.get_transform(token, "unquote")?.data,
-            Token::UnquoteSplicing => self.get_transform(token, "unquote-splicing")?.data,
+            Token::Quote => self.get_transform(token, "quote")?,
+            Token::Quasiquote => self.get_transform(token, "quasiquote")?,
+            Token::Unquote => self.get_transform(token, "unquote")?,
+            Token::UnquoteSplicing => self.get_transform(token, "unquote-splicing")?,
+
+            // datum labels
+            Token::Label(n) => self.get_labelled(n)?,
+            Token::LabelReference(n) if self.labels.contains(&n) => {self.advance(); Datum::Reference(n).with_span(span)},
+            Token::LabelReference(n) => return located_error!(ProcessorError::UndefinedLabel(n), location),
 
             Token::RightParen => return located_error!(ProcessorError::UnmatchedParentheses, location),
             _ => return located_error!(ProcessorError::UnexpectedToken(token), location),
-        }.with_location(location)))
+        }))
+    }
+
+    // 2.2 `#;` comments out the datum that follows it.
+    fn skip_datum_comments(&mut self) -> Result<()> {
+        self.reset();
+        while let Some(Located{data: Ok(Token::DatumComment), span}) = self.peek() {
+            self.reset();
+            self.advance();
+            if self.get_next_datum()?.is_none() {
+                return located_error!(ProcessorError::UnexpectedEnd, span.start);
+            }
+        }
+        self.reset();
+        Ok(())
+    }
+
+    fn get_labelled(&mut self, n: usize) -> Result<Located<Datum>> {
+        let label = self.advance();
+        debug_assert_eq!(label.clone().map(|l| l.data), Some(Ok(Token::Label(n))));
+        let location = label.unwrap().location();
+
+        self.labels.insert(n);
+        self.open.push(n);
+        let datum = self.get_next_datum()?;
+        self.open.pop();
+        match datum {
+            None => located_error!(ProcessorError::UnexpectedEnd, location),
+            // the label would stand for itself, as in `#0=#0#` or `#0=#1=#0#`
+            Some(Located{data: Datum::Reference(m), ..}) if m == n || self.open.contains(&m) => {
+                located_error!(ProcessorError::UndefinedLabel(m), location)
+            },
+            Some(datum) => {
+                let span = Span{start: location, end: datum.span.end};
+                Ok(Datum::Labelled(n, Box::new(datum)).with_span(span))
+            },
+        }
     }
 
     fn get_transform(&mut self, token: Token, symbol: &str) -> Result<Located<Datum>> {
         let start = self.advance();
         debug_assert_eq!(start.clone().map(|l| l.data), Some(Ok(token)));
-        let start_location = start.unwrap().location;
+        let start_span = start.unwrap().span;
 
         let inner = self.get_next_datum()?;
         match inner {
-            None => located_error!(ProcessorError::UnexpectedEnd, start_location),
+            None => located_error!(ProcessorError::UnexpectedEnd, start_span.start),
+            // the abbreviation spans its prefix and datum, the rest of the
+            // list just the datum
             Some(datum) => Ok(Datum::Pair(Box::new(DatumPair::Some(
-                Datum::Symbol(symbol.to_string()).with_location(start_location),
+                Datum::Symbol(symbol.to_string()).with_span(start_span),
                 Datum::Pair(Box::new(DatumPair::Some(
                     datum.clone(),
-                    Datum::Pair(Box::new(DatumPair::Empty)).with_location(datum.location),
-                ))).with_location(datum.location)
-            ))).with_location(start_location)),
+                    Datum::Pair(Box::new(DatumPair::Empty)).with_location(datum.span.end),
+                ))).with_span(datum.span)
+            ))).with_span(Span{start: start_span.start, end: datum.span.end})),
         }
     }
 
     fn get_bytevector(&mut self) -> Result<Located<Datum>> {
         let leftveccon = self.advance();
-        debug_assert_eq!(leftveccon.clone().map(|l| l.data), Some(Ok(Token::VecConsIntro)));
-        let pair_location = leftveccon.unwrap().location;
+        debug_assert_eq!(leftveccon.clone().map(|l| l.data), Some(Ok(Token::ByteVecConsIntro)));
+        let pair_location = leftveccon.unwrap().location();
 
         let mut bytes = vec![];
-        return Ok(loop {
+        loop {
+            self.skip_datum_comments()?;
             match self.advance() {
-                None => return located_error!(ProcessorError::UnexpectedEnd, panic!("TODO")),
-                Some(Located{data, location}) => match 
-                    data.map_err(|e| ProcessorError::LexerError(e).with_location(location))? 
+                None => return located_error!(ProcessorError::UnexpectedEnd, pair_location),
+                Some(Located{data, span}) => match 
+                    data.map_err(|e| ProcessorError::LexerError(e).with_span(span))? 
                 {
-                    Token::RightParen => break Datum::ByteVector(bytes),
+                    Token::RightParen => {
+                        break Ok(Datum::ByteVector(bytes).with_span(Span{start: pair_location, end: span.end}))
+                    },
                     Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(i)))) => {
                         match i {
                             0..=255 => bytes.push(i as u8),
                             i => return located_error!(
                                 ProcessorError::UnexpectedToken(Token::Primitive(Primitive::Complex(Complex::Real(Real::Integer(i))))),
-                                location
+                                span.start
                             ),
                         }
                     },
-                    token => return located_error!(ProcessorError::UnexpectedToken(token), location),
+                    token => return located_error!(ProcessorError::UnexpectedToken(token), span.start),
                 },
             }
-        }.with_location(pair_location))
+        }
     }
 
     fn get_vector(&mut self) -> Result<Located<Datum>> {
         let leftveccon = self.advance();
         debug_assert_eq!(leftveccon.clone().map(|l| l.data), Some(Ok(Token::VecConsIntro)));
-        let pair_location = leftveccon.unwrap().location;
+        let pair_location = leftveccon.unwrap().location();
 
         let mut datums = vec![];
-        while self.peek_without_location() != Some(Ok(Token::RightParen)) {
+        loop {
+            self.skip_datum_comments()?;
+            if self.peek_without_location() == Some(Ok(Token::RightParen)) {
+                break;
+            }
             self.reset();
             match self.get_next_datum()? {
-                None => return located_error!(ProcessorError::UnexpectedEnd, panic!("TODO")),
+                None => return located_error!(ProcessorError::UnexpectedEnd, pair_location),
                 Some(datum) => datums.push(datum),
             }
         }
+        let right_paren = self.advance().unwrap();
 
-        Ok(Datum::Vector(datums).with_location(pair_location))
+        Ok(Datum::Vector(datums).with_span(Span{start: pair_location, end: right_paren.span.end}))
     }
 
     fn get_pair(&mut self) -> Result<Located<Datum>> {
         let left_paren = self.advance();
         debug_assert_eq!(left_paren.clone().map(|l| l.data), Some(Ok(Token::LeftParen)));
-        let pair_location = left_paren.unwrap().location;
+        let pair_location = left_paren.unwrap().location();
 
         let mut head = Box::new(DatumPair::Empty);
         let mut tail = head.as_mut();
 
         let mut encounter_period = false;
-        return loop {
+        loop {
+            self.skip_datum_comments()?;
             match self.peek() {
-                Some(Located{data: token, location}) => match
-                    token.map_err(|e| ProcessorError::LexerError(e).with_location(location))?
+                Some(Located{data: token, span}) => match
+                    token.map_err(|e| ProcessorError::LexerError(e).with_span(span))?
                 {
                     Token::Period => {
-                        if encounter_period {
+                        // a period needs a datum on each side of it
+                        if encounter_period || *tail == DatumPair::Empty {
                             return located_error!(
                                 ProcessorError::UnexpectedToken(Token::Period),
-                                location
+                                span.start
                             );
                         }
                         
@@ -164,49 +230,106 @@ impl<TokenIter: Iterator<Item = TResult>> Processor<TokenIter> {
                         encounter_period = true;
                         continue;
                     },
-                    Token::RightParen => {self.advance(); break Ok(Datum::Pair(head).with_location(pair_location))}, // TODO
+                    Token::RightParen if encounter_period => {
+                        return located_error!(ProcessorError::UnexpectedToken(Token::RightParen), span.start);
+                    },
+                    Token::RightParen => {
+                        self.advance();
+                        break Ok(Datum::Pair(head).with_span(Span{start: pair_location, end: span.end}))
+                    },
                     _ => {
                         self.reset();
-                        let Located{data: element, location} = self.get_next_datum()?
-                            .ok_or(ProcessorError::UnexpectedEnd.with_location(location))?;
+                        let element = self.get_next_datum()?
+                            .ok_or(ProcessorError::UnexpectedEnd.with_span(span))?;
+                        // the tails of the list start at their first element
+                        // and, like the empty list ending it, are not
+                        // extended to the closing parenthesis
+                        let empty = Datum::Pair(Box::new(DatumPair::Empty)).with_location(element.span.end);
                         
                         match tail {
                             DatumPair::Empty => {
-                                head = Box::new(DatumPair::Some(
-                                    element.with_location(location), 
-                                    Datum::Pair(Box::new(DatumPair::Empty)).with_location(location)
-                                ));
+                                head = Box::new(DatumPair::Some(element, empty));
                                 tail = head.as_mut();
                             },
-                            DatumPair::Some(car, cdr) => {
+                            DatumPair::Some(_, cdr) => {
                                 if encounter_period {
-                                    *cdr = element.with_location(location);
-                                    let right_paren = self.advance();
-                                    debug_assert_eq!(right_paren.clone().map(|l| l.data), Some(Ok(Token::RightParen)));
-                                    break Ok(Datum::Pair(head).with_location(pair_location));
+                                    *cdr = element;
+                                    self.skip_datum_comments()?;
+                                    // the datum after the period ends the list
+                                    break match self.advance() {
+                                        None => located_error!(ProcessorError::UnexpectedEnd, pair_location),
+                                        Some(Located{data: Err(e), span}) => Err(ProcessorError::LexerError(e).with_span(span)),
+                                        Some(Located{data: Ok(Token::RightParen), span}) => {
+                                            Ok(Datum::Pair(head).with_span(Span{start: pair_location, end: span.end}))
+                                        },
+                                        Some(Located{data: Ok(token), span}) => {
+                                            located_error!(ProcessorError::UnexpectedToken(token), span.start)
+                                        },
+                                    };
                                 }
 
-                                assert_eq!((*cdr).data, Datum::Pair(Box::new(DatumPair::Empty)));
+                                assert_eq!(cdr.data, Datum::Pair(Box::new(DatumPair::Empty)));
 
-                                let new_tail = 
-                                    DatumPair::Some(
-                                        element.with_location(location), 
-                                        Datum::Pair(Box::new(DatumPair::Empty)).with_location(location)
-                                    );
-                                *cdr = Datum::Pair(Box::new(new_tail)).with_location(location);
+                                let span = element.span;
+                                *cdr = Datum::Pair(Box::new(DatumPair::Some(element, empty))).with_span(span);
                                 tail = if let Located{
                                     data: Datum::Pair(p), 
-                                    location
+                                    ..
                                 } = cdr { p } else { panic!() }
                             }
                         }
-
-                        ()
                     }
                 },
                 None => return Err(ProcessorError::UnexpectedEnd.with_location(pair_location)),
             }
-        };
+        }
+    }
+}
+
+
+#[test]
+fn spans() {
+    use super::{Lexer, Location};
+
+    let read = |text: &str| Processor::from(Lexer::new(text.chars())).map(|datum| datum.span.range()).collect::<Vec<_>>();
+    assert_eq!(read("(a (b . c) d) #(1 2) #u8(3)"), vec![0..13, 14..20, 21..27]);
+    assert_eq!(read("'a `(1) #0=(x . #0#)"), vec![0..2, 3..7, 8..20]);
+    assert_eq!(read("( a #;b )"), vec![0..9]);
+
+    let datum = Processor::from(Lexer::new("λ (x\n y)".chars())).nth(1).unwrap();
+    assert_eq!(datum.span, Span{
+        start: Location{row: 0, col: 2, offset: 3, ..Location::default()},
+        end: Location{row: 1, col: 3, offset: 9, ..Location::default()},
+    });
+    match datum.data {
+        Ok(Datum::Pair(pair)) => match *pair {
+            DatumPair::Some(car, cdr) => {
+                assert_eq!(car.span.range(), 4..5);
+                assert_eq!(cdr.span.range(), 7..8);
+            },
+            DatumPair::Empty => panic!(),
+        },
+        _ => panic!(),
     }
+
+    let error = Processor::from(Lexer::new("(a\n ".chars())).next().unwrap();
+    assert_eq!(error.data, Err(ProcessorError::UnexpectedEnd));
+    assert_eq!(error.span, Span::at(Location::default()));
 }
 
+#[test]
+fn dotted_pairs() {
+    use super::{Lexer, Location};
+
+    let read = |text: &str| Processor::from(Lexer::new(text.chars())).next().unwrap();
+    let at = |col: u32| Span::at(Location{col, offset: col as usize, ..Location::default()});
+    assert_eq!(read("(a . b").data, Err(ProcessorError::UnexpectedEnd));
+    assert_eq!(read("(a . b").span, at(0));
+    assert_eq!(read("(a . b c)").data, Err(ProcessorError::UnexpectedToken(Token::Identifier("c".to_string()))));
+    assert_eq!(read("(a . b c)").span, at(7));
+    assert_eq!(read("(. a)").data, Err(ProcessorError::UnexpectedToken(Token::Period)));
+    assert_eq!(read("(. a)").span, at(1));
+    assert_eq!(read("(a . )").data, Err(ProcessorError::UnexpectedToken(Token::RightParen)));
+    assert_eq!(read("(a . )").span, at(5));
+    assert_eq!(read("(a . #;b c #;d)").data.map(|datum| datum.to_string()), Ok("(a . c)".to_string()));
+}
@@ -0,0 +1,920 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+use super::{
+    Datum, DatumPair, Primitive, Complex, Real, Located, Location, Span, ToLocated, Environment, Continuation,
+    RuntimeError, ProcessorError, Lexer, LexerOptions, Processor, Printer, Style,
+};
+use super::printer::cycles;
+use super::expression::Lambda;
+
+// Runtime values produced by the interpreter. Unlike `Datum`, values carry no
+// source location and compound values are shared, mutable heap objects.
+#[derive(Clone)]
+pub enum Value {
+    Boolean(bool),
+    Number(Complex),
+    Character(char),
+    String(Rc<RefCell<String>>),
+    Symbol(String),
+    ByteVector(Rc<RefCell<Vec<u8>>>),
+
+    Null,
+    Pair(Rc<Pair>),
+    Vector(Rc<RefCell<Vec<Value>>>),
+
+    Procedure(Procedure),
+    Port(Rc<Port>),
+    Record(Rc<Record>),
+    Error(Rc<ErrorObject>),
+
+    // zero or several values returned by `values`; a single value is never wrapped
+    Values(Rc<[Value]>),
+    Unspecified,
+    Eof,
+}
+
+// 6.4 Pairs and lists
+pub struct Pair {
+    car: RefCell<Value>,
+    cdr: RefCell<Value>,
+}
+
+impl Pair {
+    pub fn car(&self) -> Value {
+        self.car.borrow().clone()
+    }
+
+    pub fn cdr(&self) -> Value {
+        self.cdr.borrow().clone()
+    }
+
+    pub fn set_car(&self, value: Value) {
+        *self.car.borrow_mut() = value;
+    }
+
+    pub fn set_cdr(&self, value: Value) {
+        *self.cdr.borrow_mut() = value;
+    }
+}
+
+// Unlink long cdr chains one pair at a time instead of recursing through them.
+impl Drop for Pair {
+    fn drop(&mut self) {
+        let mut next = std::mem::replace(self.cdr.get_mut(), Value::Null);
+        while let Value::Pair(pair) = next {
+            match Rc::try_unwrap(pair) {
+                Ok(mut pair) => next = std::mem::replace(pair.cdr.get_mut(), Value::Null),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Procedure {
+    Closure(Rc<Closure>),
+    Native(Rc<NativeProcedure>),
+    Control(Control),
+    Continuation(Rc<Continuation>),
+}
+
+impl Procedure {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Procedure::Closure(closure) => closure.lambda.name.as_deref(),
+            Procedure::Native(native) => Some(&native.name),
+            Procedure::Control(control) => Some(control.name()),
+            Procedure::Continuation(_) => Some("continuation"),
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            Procedure::Closure(closure) => closure.lambda.arity(),
+            Procedure::Native(native) => native.arity,
+            Procedure::Control(control) => control.arity(),
+            Procedure::Continuation(_) => Arity::AtLeast(0),
+        }
+    }
+
+    fn ptr_eq(&self, other: &Procedure) -> bool {
+        match (self, other) {
+            (Procedure::Closure(a), Procedure::Closure(b)) => Rc::ptr_eq(a, b),
+            (Procedure::Native(a), Procedure::Native(b)) => Rc::ptr_eq(a, b),
+            (Procedure::Control(a), Procedure::Control(b)) => a == b,
+            (Procedure::Continuation(a), Procedure::Continuation(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+// 6.10 Control features that need the interpreter itself, because they call
+// other procedures or replace the current continuation.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Control {
+    Apply,
+    CallWithCurrentContinuation,
+    CallWithValues,
+    DynamicWind,
+    WithExceptionHandler,
+    RaiseContinuable,
+}
+
+impl Control {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Control::Apply => "apply",
+            Control::CallWithCurrentContinuation => "call-with-current-continuation",
+            Control::CallWithValues => "call-with-values",
+            Control::DynamicWind => "dynamic-wind",
+            Control::WithExceptionHandler => "with-exception-handler",
+            Control::RaiseContinuable => "raise-continuable",
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            Control::Apply => Arity::AtLeast(2),
+            Control::CallWithCurrentContinuation => Arity::Exactly(1),
+            Control::CallWithValues => Arity::Exactly(2),
+            Control::DynamicWind => Arity::Exactly(3),
+            Control::WithExceptionHandler => Arity::Exactly(2),
+            Control::RaiseContinuable => Arity::Exactly(1),
+        }
+    }
+}
+
+pub struct Closure {
+    pub(crate) lambda: Rc<Lambda>,
+    pub(crate) environment: Environment,
+}
+
+type NativeFunction = dyn Fn(&[Value]) -> std::result::Result<Value, RuntimeError>;
+
+// A procedure implemented in Rust. The interpreter checks the argument count
+// against `arity` before calling `function`.
+pub struct NativeProcedure {
+    pub name: String,
+    pub arity: Arity,
+    function: Box<NativeFunction>,
+}
+
+impl NativeProcedure {
+    pub fn new<F>(name: &str, arity: Arity, function: F) -> Self
+    where
+        F: Fn(&[Value]) -> std::result::Result<Value, RuntimeError> + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            arity,
+            function: Box::new(function),
+        }
+    }
+
+    pub fn call(&self, arguments: &[Value]) -> std::result::Result<Value, RuntimeError> {
+        (self.function)(arguments)
+    }
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    // required and optional arguments: from the first to the second count
+    Between(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exactly(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+            Arity::Between(min, max) => (min..=max).contains(&count),
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arity::Exactly(n) => write!(f, "{}", n),
+            Arity::AtLeast(n) => write!(f, "at least {}", n),
+            Arity::Between(min, max) => write!(f, "{} to {}", min, max),
+        }
+    }
+}
+
+// 6.13 Input and output
+pub struct Port {
+    kind: PortKind,
+    open: Cell<bool>,
+}
+
+enum PortKind {
+    // the text, the position reached and, for `read`, whether a
+    // `#!fold-case` has been read so far
+    TextualInput(RefCell<(Vec<char>, usize, bool)>),
+    TextualOutput(RefCell<String>),
+    BinaryInput(RefCell<(Vec<u8>, usize)>),
+    BinaryOutput(RefCell<Vec<u8>>),
+    // textual output forwarded to a host writer such as stdout
+    Writer(RefCell<Box<dyn Write>>),
+}
+
+impl Port {
+    fn new(kind: PortKind) -> Self {
+        Self {
+            kind,
+            open: Cell::new(true),
+        }
+    }
+
+    pub fn input_string(text: &str) -> Self {
+        Self::new(PortKind::TextualInput(RefCell::new((text.chars().collect(), 0, false))))
+    }
+
+    pub fn output_string() -> Self {
+        Self::new(PortKind::TextualOutput(RefCell::new(String::new())))
+    }
+
+    pub fn input_bytes(bytes: &[u8]) -> Self {
+        Self::new(PortKind::BinaryInput(RefCell::new((bytes.to_vec(), 0))))
+    }
+
+    pub fn output_bytes() -> Self {
+        Self::new(PortKind::BinaryOutput(RefCell::new(vec![])))
+    }
+
+    pub fn writer<W: Write + 'static>(writer: W) -> Self {
+        Self::new(PortKind::Writer(RefCell::new(Box::new(writer))))
+    }
+
+    pub fn is_input(&self) -> bool {
+        matches!(self.kind, PortKind::TextualInput(_) | PortKind::BinaryInput(_))
+    }
+
+    pub fn is_output(&self) -> bool {
+        !self.is_input()
+    }
+
+    pub fn is_textual(&self) -> bool {
+        !self.is_binary()
+    }
+
+    pub fn is_binary(&self) -> bool {
+        matches!(self.kind, PortKind::BinaryInput(_) | PortKind::BinaryOutput(_))
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.get()
+    }
+
+    pub fn close(&self) {
+        if let PortKind::Writer(writer) = &self.kind {
+            writer.borrow_mut().flush().ok();
+        }
+        self.open.set(false);
+    }
+
+    // Returns None at the end of input, or when this is not an open textual
+    // input port.
+    pub fn read_char(&self) -> Option<char> {
+        let ch = self.peek_char()?;
+        if let PortKind::TextualInput(input) = &self.kind {
+            input.borrow_mut().1 += 1;
+        }
+        Some(ch)
+    }
+
+    pub fn peek_char(&self) -> Option<char> {
+        match &self.kind {
+            PortKind::TextualInput(input) if self.is_open() => {
+                let (text, position, _) = &*input.borrow();
+                text.get(*position).copied()
+            },
+            _ => None,
+        }
+    }
+
+    // Reads the next datum, consuming only its characters. Returns None at
+    // the end of input; a malformed datum consumes the rest of the input.
+    pub fn read_datum(&self) -> Option<std::result::Result<Datum, Located<ProcessorError>>> {
+        let input = match &self.kind {
+            PortKind::TextualInput(input) if self.is_open() => input,
+            _ => return None,
+        };
+        let (text, position, fold_case) = &mut *input.borrow_mut();
+        let rest = &text[*position..];
+        let options = LexerOptions{fold_case: *fold_case, ..LexerOptions::default()};
+        let mut lexer = Lexer::with_options(rest.iter().copied(), options);
+        let result = Processor::from(&mut lexer).get_next_datum().transpose()?;
+        let start = location_after(&text[..*position]);
+        Some(match result {
+            Ok(datum) => {
+                *position += consumed(rest, lexer.location());
+                // 2.1 the directive holds for the rest of the port
+                *fold_case = lexer.is_folding_case();
+                Ok(datum.data)
+            },
+            Err(Located{data, span}) => {
+                *position = text.len();
+                // reported relative to the whole input rather than the read
+                Err(data.with_span(Span{start: relocate(start, span.start), end: relocate(start, span.end)}))
+            },
+        })
+    }
+
+    pub fn read_u8(&self) -> Option<u8> {
+        match &self.kind {
+            PortKind::BinaryInput(input) if self.is_open() => {
+                let (bytes, position) = &mut *input.borrow_mut();
+                let byte = bytes.get(*position).copied();
+                *position += byte.is_some() as usize;
+                byte
+            },
+            _ => None,
+        }
+    }
+
+    // Returns false when this is not an open textual output port or the
+    // underlying writer fails.
+    pub fn write_str(&self, text: &str) -> bool {
+        match &self.kind {
+            PortKind::TextualOutput(output) if self.is_open() => {
+                output.borrow_mut().push_str(text);
+                true
+            },
+            PortKind::Writer(writer) if self.is_open() => writer.borrow_mut().write_all(text.as_bytes()).is_ok(),
+            _ => false,
+        }
+    }
+
+    pub fn write_u8(&self, byte: u8) -> bool {
+        match &self.kind {
+            PortKind::BinaryOutput(output) if self.is_open() => {
+                output.borrow_mut().push(byte);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // The text written so far to a string output port.
+    pub fn output_string_contents(&self) -> Option<String> {
+        match &self.kind {
+            PortKind::TextualOutput(output) => Some(output.borrow().clone()),
+            _ => None,
+        }
+    }
+
+    pub fn output_bytes_contents(&self) -> Option<Vec<u8>> {
+        match &self.kind {
+            PortKind::BinaryOutput(output) => Some(output.borrow().clone()),
+            _ => None,
+        }
+    }
+}
+
+// The location the lexer reaches after reading `text`.
+fn location_after(text: &[char]) -> Location {
+    text.iter().fold(Location::default(), |location, ch| {
+        let offset = location.offset + ch.len_utf8();
+        match ch {
+            '\n' => Location{row: location.row + 1, col: 0, offset, ..location},
+            _ => Location{row: location.row, col: location.col + 1, offset, ..location},
+        }
+    })
+}
+
+// `location`, read from a text starting at `start`, in the whole text.
+fn relocate(start: Location, location: Location) -> Location {
+    let offset = start.offset + location.offset;
+    match location.row {
+        0 => Location{row: start.row, col: start.col + location.col, offset, ..location},
+        row => Location{row: start.row + row, col: location.col, offset, ..location},
+    }
+}
+
+// The number of characters of `text` before `location`.
+fn consumed(text: &[char], location: Location) -> usize {
+    let line_start = match location.row {
+        0 => 0,
+        row => text.iter().enumerate()
+            .filter(|(_, ch)| **ch == '\n')
+            .nth(row as usize - 1)
+            .map_or(text.len(), |(i, _)| i + 1),
+    };
+    line_start + location.col as usize
+}
+
+// 5.5 Record-type definitions
+pub struct RecordType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+pub struct Record {
+    pub record_type: Rc<RecordType>,
+    pub fields: RefCell<Vec<Value>>,
+}
+
+// 6.11 Exceptions: the objects raised by `error`, by the reader and by
+// failing primitives.
+pub struct ErrorObject {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub irritants: Vec<Value>,
+}
+
+pub enum ErrorKind {
+    // created by `error`
+    User,
+    Read(Located<ProcessorError>),
+    File,
+    // signalled by the interpreter or a native procedure
+    Runtime(Located<RuntimeError>),
+}
+
+impl ErrorObject {
+    pub fn new(message: &str, irritants: Vec<Value>) -> Self {
+        Self {
+            kind: ErrorKind::User,
+            message: message.to_string(),
+            irritants,
+        }
+    }
+
+    pub fn read(error: Located<ProcessorError>) -> Self {
+        Self {
+            message: error.data.to_string(),
+            kind: ErrorKind::Read(error),
+            irritants: vec![],
+        }
+    }
+
+    pub fn file(message: &str, filename: &str) -> Self {
+        Self {
+            kind: ErrorKind::File,
+            message: message.to_string(),
+            irritants: vec![Value::string(filename)],
+        }
+    }
+}
+
+impl From<Located<RuntimeError>> for ErrorObject {
+    fn from(error: Located<RuntimeError>) -> Self {
+        let symbol = |name: &str| Value::Symbol(name.to_string());
+        let integer = |n: usize| Value::Number(Complex::Real(Real::Integer(n as i64)));
+        let (message, irritants) = match &error.data {
+            RuntimeError::ProcessorError(_) => ("read error", vec![]),
+            RuntimeError::BadSyntax(keyword) => ("bad syntax", vec![symbol(keyword)]),
+            RuntimeError::UnboundVariable(name) => ("unbound variable", vec![symbol(name)]),
+            RuntimeError::UndefinedAssignment(name) => ("assignment to undefined variable", vec![symbol(name)]),
+            RuntimeError::NotProcedure(value) => ("not a procedure", vec![value.clone()]),
+            RuntimeError::WrongArgumentCount(_, count) => ("wrong number of arguments", vec![integer(*count)]),
+            RuntimeError::WrongType(i, expected) => ("wrong type argument", vec![integer(*i), Value::string(expected)]),
+            RuntimeError::IndexOutOfRange(k) => ("index out of range", vec![integer(*k)]),
+            RuntimeError::DivisionByZero => ("division by zero", vec![]),
+            RuntimeError::NumberTooLarge => ("exact number too large", vec![]),
+            RuntimeError::RecursionLimitExceeded => ("recursion limit exceeded", vec![]),
+            RuntimeError::Raised(value) => ("uncaught exception", vec![value.clone()]),
+            RuntimeError::HandlerReturned(value) => ("handler returned from non-continuable raise", vec![value.clone()]),
+        };
+        Self {
+            message: message.to_string(),
+            irritants,
+            kind: ErrorKind::Runtime(error),
+        }
+    }
+}
+
+impl Value {
+    pub fn cons(car: Value, cdr: Value) -> Value {
+        Value::Pair(Rc::new(Pair{car: RefCell::new(car), cdr: RefCell::new(cdr)}))
+    }
+
+    pub fn list<I>(values: I) -> Value
+    where
+        I: IntoIterator<Item = Value>,
+        I::IntoIter: DoubleEndedIterator,
+    {
+        values.into_iter().rev().fold(Value::Null, |cdr, car| Value::cons(car, cdr))
+    }
+
+    pub fn string(s: &str) -> Value {
+        Value::String(Rc::new(RefCell::new(s.to_string())))
+    }
+
+    pub fn vector(values: Vec<Value>) -> Value {
+        Value::Vector(Rc::new(RefCell::new(values)))
+    }
+
+    pub fn bytevector(bytes: Vec<u8>) -> Value {
+        Value::ByteVector(Rc::new(RefCell::new(bytes)))
+    }
+
+    pub fn native<F>(name: &str, arity: Arity, function: F) -> Value
+    where
+        F: Fn(&[Value]) -> std::result::Result<Value, RuntimeError> + 'static,
+    {
+        Value::Procedure(Procedure::Native(Rc::new(NativeProcedure::new(name, arity, function))))
+    }
+
+    // 6.10 values: a single value stands for itself.
+    pub fn values(mut values: Vec<Value>) -> Value {
+        if values.len() == 1 {
+            values.pop().unwrap()
+        } else {
+            Value::Values(values.into())
+        }
+    }
+
+    // Collects the elements of a proper list, or returns None for improper
+    // and circular lists.
+    pub fn list_to_vec(&self) -> Option<Vec<Value>> {
+        let mut elements = vec![];
+        let mut current = self.clone();
+        // advances at half speed; meeting it again means the list is circular
+        let mut slow = self.clone();
+        loop {
+            current = match current {
+                Value::Null => return Some(elements),
+                Value::Pair(pair) => {
+                    elements.push(pair.car());
+                    pair.cdr()
+                },
+                _ => return None,
+            };
+            if elements.len() % 2 == 0 {
+                slow = match slow {
+                    Value::Pair(pair) => pair.cdr(),
+                    _ => unreachable!(),
+                };
+                if let (Value::Pair(a), Value::Pair(b)) = (&slow, &current) {
+                    if Rc::ptr_eq(a, b) {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    // Everything except #f counts as true in conditional expressions.
+    pub fn is_true(&self) -> bool {
+        !matches!(self, Value::Boolean(false))
+    }
+
+    // 6.1 eq?
+    pub fn is_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            // small exact integers are eq? when eqv?, as they would be
+            // immediate values elsewhere; other numbers have no identity
+            (Value::Number(Complex::Real(Real::Integer(_))), Value::Number(Complex::Real(Real::Integer(_)))) => self.eqv(other),
+            (Value::Number(_), Value::Number(_)) => false,
+            (Value::Values(a), Value::Values(b)) => Rc::ptr_eq(a, b),
+            _ => self.eqv(other),
+        }
+    }
+
+    // 6.1 eqv?
+    pub fn eqv(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => eqv_number(a, b),
+            (Value::Character(a), Value::Character(b)) => a == b,
+            (Value::Symbol(a), Value::Symbol(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::Unspecified, Value::Unspecified) => true,
+            (Value::Eof, Value::Eof) => true,
+            (Value::String(a), Value::String(b)) => Rc::ptr_eq(a, b),
+            (Value::ByteVector(a), Value::ByteVector(b)) => Rc::ptr_eq(a, b),
+            (Value::Pair(a), Value::Pair(b)) => Rc::ptr_eq(a, b),
+            (Value::Vector(a), Value::Vector(b)) => Rc::ptr_eq(a, b),
+            (Value::Procedure(a), Value::Procedure(b)) => a.ptr_eq(b),
+            (Value::Port(a), Value::Port(b)) => Rc::ptr_eq(a, b),
+            (Value::Record(a), Value::Record(b)) => Rc::ptr_eq(a, b),
+            (Value::Error(a), Value::Error(b)) => Rc::ptr_eq(a, b),
+            (Value::Values(a), Value::Values(b)) => a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.eqv(b)),
+            _ => false,
+        }
+    }
+
+    // Converts back to reader data, e.g. for printing. Procedures, ports,
+    // records and the other opaque objects have no external representation,
+    // so any value containing one yields None. Pairs and vectors on a cycle
+    // get datum labels, so circular structure converts to a finite datum.
+    pub fn to_datum(&self) -> Option<Datum> {
+        self.to_labelled_datum(&cycles(self), &mut HashMap::new())
+    }
+
+    // `labels` numbers the objects of `cycles` in the order they are reached.
+    fn to_labelled_datum(&self, cycles: &HashSet<usize>, labels: &mut HashMap<usize, usize>) -> Option<Datum> {
+        let location = Location::default();
+        let labelled = |n: Option<usize>, datum: Datum| match n {
+            Some(n) => Datum::Labelled(n, Box::new(datum.with_location(location))),
+            None => datum,
+        };
+        Some(match self {
+            Value::Boolean(b) => Datum::Primitive(Primitive::Boolean(*b)),
+            Value::Number(c) => Datum::Primitive(Primitive::Complex(c.clone())),
+            Value::Character(c) => Datum::Primitive(Primitive::Character(*c)),
+            Value::String(s) => Datum::Primitive(Primitive::String(s.borrow().clone())),
+            Value::Symbol(s) => Datum::Symbol(s.clone()),
+            Value::ByteVector(bytes) => Datum::ByteVector(bytes.borrow().clone()),
+            Value::Null => Datum::Pair(Box::new(DatumPair::Empty)),
+            Value::Pair(_) => {
+                let mut cars = vec![];
+                let mut current = self.clone();
+                let tail = loop {
+                    let pair = match current {
+                        Value::Pair(pair) => pair,
+                        tail => break tail.to_labelled_datum(cycles, labels)?,
+                    };
+                    match datum_label(Rc::as_ptr(&pair) as usize, cycles, labels) {
+                        Ok(n) => cars.push((n, pair.car().to_labelled_datum(cycles, labels)?)),
+                        Err(n) => break Datum::Reference(n),
+                    }
+                    current = pair.cdr();
+                };
+                cars.into_iter().rev().fold(tail, |cdr, (n, car)| labelled(n, Datum::Pair(Box::new(DatumPair::Some(
+                    car.with_location(location),
+                    cdr.with_location(location),
+                )))))
+            },
+            Value::Vector(values) => match datum_label(Rc::as_ptr(values) as usize, cycles, labels) {
+                Ok(n) => labelled(n, Datum::Vector(
+                    values.borrow().iter()
+                        .map(|v| v.to_labelled_datum(cycles, labels).map(|d| d.with_location(location)))
+                        .collect::<Option<_>>()?
+                )),
+                Err(n) => Datum::Reference(n),
+            },
+            Value::Procedure(_) |
+            Value::Port(_) |
+            Value::Record(_) |
+            Value::Error(_) |
+            Value::Values(_) |
+            Value::Unspecified |
+            Value::Eof => return None,
+        })
+    }
+}
+
+// The label of an object reached by `Value::to_datum`: a new number when it is
+// on a cycle and reached for the first time, or `Err` with the number it got
+// before, which the datum refers back to.
+fn datum_label(
+    address: usize,
+    cycles: &HashSet<usize>,
+    labels: &mut HashMap<usize, usize>,
+) -> std::result::Result<Option<usize>, usize> {
+    match labels.get(&address) {
+        Some(n) => Err(*n),
+        None if cycles.contains(&address) => {
+            let n = labels.len();
+            labels.insert(address, n);
+            Ok(Some(n))
+        },
+        None => Ok(None),
+    }
+}
+
+// Panics if the datum refers to a label it never defines, which no datum from
+// the processor does.
+impl From<&Datum> for Value {
+    fn from(datum: &Datum) -> Self {
+        match Labels::default().resolve(datum, Span::default()) {
+            Ok(value) => value,
+            Err(_) => panic!("undefined datum label in {}", datum),
+        }
+    }
+}
+
+// The objects named by datum labels while converting the data of one
+// top-level datum; `#n#` becomes the very object labelled `#n=`, so the value
+// shares structure the same way.
+#[derive(Default)]
+pub(crate) struct Labels(HashMap<usize, Value>);
+
+impl Labels {
+    pub(crate) fn value(&mut self, datum: &Located<Datum>) -> std::result::Result<Value, Located<ProcessorError>> {
+        self.resolve(&datum.data, datum.span)
+    }
+
+    fn resolve(&mut self, datum: &Datum, span: Span) -> std::result::Result<Value, Located<ProcessorError>> {
+        Ok(match datum {
+            Datum::Primitive(Primitive::Boolean(b)) => Value::Boolean(*b),
+            Datum::Primitive(Primitive::Complex(c)) => Value::Number(c.clone()),
+            Datum::Primitive(Primitive::Character(c)) => Value::Character(*c),
+            Datum::Primitive(Primitive::String(s)) => Value::string(s),
+            Datum::Symbol(s) => Value::Symbol(s.clone()),
+            Datum::ByteVector(bytes) => Value::bytevector(bytes.clone()),
+            Datum::Vector(datums) => Value::vector(datums.iter().map(|d| self.value(d)).collect::<std::result::Result<_, _>>()?),
+            // a label defined outside this data, such as on code, has no value
+            Datum::Reference(n) => match self.0.get(n) {
+                Some(object) => object.clone(),
+                None => return Err(ProcessorError::UndefinedLabel(*n).with_span(span)),
+            },
+            Datum::Labelled(n, datum) => {
+                // `#0=#1=...` names one object twice
+                let mut names = vec![*n];
+                let mut datum = datum.as_ref();
+                while let Datum::Labelled(n, inner) = &datum.data {
+                    names.push(*n);
+                    datum = inner;
+                }
+                // a labelled pair or vector exists before its contents, which
+                // may refer back to it
+                match &datum.data {
+                    Datum::Pair(pair) if **pair != DatumPair::Empty => {
+                        let object = Value::cons(Value::Unspecified, Value::Null);
+                        self.name(&names, &object);
+                        if let (Value::Pair(object), DatumPair::Some(car, cdr)) = (&object, pair.as_ref()) {
+                            object.set_car(self.value(car)?);
+                            object.set_cdr(self.value(cdr)?);
+                        }
+                        object
+                    },
+                    Datum::Vector(datums) => {
+                        let object = Value::vector(vec![]);
+                        self.name(&names, &object);
+                        if let Value::Vector(values) = &object {
+                            let contents = datums.iter().map(|d| self.value(d)).collect::<std::result::Result<_, _>>()?;
+                            *values.borrow_mut() = contents;
+                        }
+                        object
+                    },
+                    _ => {
+                        let object = self.value(datum)?;
+                        self.name(&names, &object);
+                        object
+                    },
+                }
+            },
+            Datum::Pair(_) => {
+                // walk the cdr chain iteratively so long lists don't recurse
+                let mut cars = vec![];
+                let mut current = (datum, span);
+                while let Datum::Pair(pair) = current.0 {
+                    match pair.as_ref() {
+                        DatumPair::Some(car, cdr) => {
+                            cars.push(self.value(car)?);
+                            current = (&cdr.data, cdr.span);
+                        },
+                        DatumPair::Empty => break,
+                    }
+                }
+                let tail = match current {
+                    (Datum::Pair(_), _) => Value::Null,
+                    (datum, span) => self.resolve(datum, span)?,
+                };
+                cars.into_iter().rev().fold(tail, |cdr, car| Value::cons(car, cdr))
+            },
+        })
+    }
+
+    fn name(&mut self, names: &[usize], object: &Value) {
+        for n in names {
+            self.0.insert(*n, object.clone());
+        }
+    }
+}
+
+// 6.1 numbers are eqv? when their parts are of the same exactness and equal,
+// with floats compared bit for bit so that 0.0 and -0.0 differ.
+fn eqv_number(a: &Complex, b: &Complex) -> bool {
+    let real = |a: &Real, b: &Real| match (a, b) {
+        (Real::Float(x), Real::Float(y)) => x.to_bits() == y.to_bits(),
+        (a, b) => a == b,
+    };
+    match (a, b) {
+        (Complex::Real(a), Complex::Real(b)) => real(a, b),
+        (Complex::Imaginary(a), Complex::Imaginary(b)) => real(a, b),
+        (Complex::Complex(a, b), Complex::Complex(c, d)) => real(a, c) && real(b, d),
+        _ => false,
+    }
+}
+
+impl From<&Located<Datum>> for Value {
+    fn from(datum: &Located<Datum>) -> Self {
+        Value::from(&datum.data)
+    }
+}
+
+// equal? semantics: pairs, strings, vectors and bytevectors compare
+// structurally, everything else as eqv?.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        equal(self, other, &mut HashSet::new())
+    }
+}
+
+// `compared` holds the addresses of the pairs and vectors already being
+// compared; they are taken to be equal, so that comparing circular
+// structures ends, and any difference is still found elsewhere.
+fn equal(a: &Value, b: &Value, compared: &mut HashSet<(usize, usize)>) -> bool {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    loop {
+        match (&a, &b) {
+            (Value::Pair(x), Value::Pair(y)) => {
+                if Rc::ptr_eq(x, y) || !compared.insert((Rc::as_ptr(x) as usize, Rc::as_ptr(y) as usize)) {
+                    return true;
+                }
+                if !equal(&x.car(), &y.car(), compared) {
+                    return false;
+                }
+                let (x, y) = (x.cdr(), y.cdr());
+                a = x;
+                b = y;
+            },
+            (Value::String(x), Value::String(y)) => return *x.borrow() == *y.borrow(),
+            (Value::ByteVector(x), Value::ByteVector(y)) => return *x.borrow() == *y.borrow(),
+            (Value::Vector(x), Value::Vector(y)) => {
+                if Rc::ptr_eq(x, y) || !compared.insert((Rc::as_ptr(x) as usize, Rc::as_ptr(y) as usize)) {
+                    return true;
+                }
+                let (x, y) = (x.borrow(), y.borrow());
+                return x.len() == y.len() && x.iter().zip(y.iter()).all(|(x, y)| equal(x, y, compared));
+            },
+            _ => return a.eqv(&b),
+        }
+    }
+}
+
+// Values print as `write` does.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Printer::new(self, Style::Write))
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[test]
+fn datum_conversion() {
+    let text = "(a (1 . #\\b) #(\"c\" #u8(1 2)) . #t)";
+    let datum = Processor::from(Lexer::new(text.chars())).next().unwrap().data.unwrap();
+    let value = Value::from(&datum);
+    assert_eq!(Value::from(&value.to_datum().unwrap()), value);
+
+    let procedure = Value::native("f", Arity::Exactly(0), |_| Ok(Value::Unspecified));
+    assert!(Value::list(vec![Value::Null, procedure]).to_datum().is_none());
+    assert!(Value::Eof.to_datum().is_none());
+
+    // labels give shared and circular structure both ways
+    let read = |text: &str| Value::from(&Processor::from(Lexer::new(text.chars())).next().unwrap().data.unwrap());
+    let cycle = read("#0=(1 #1=#(#1# #0#) . #0#)");
+    assert_eq!(cycle.to_datum().unwrap().to_string(), "#0=(1 #1=#(#1# #0#) . #0#)");
+    let shared = read("(#0=(x) #0# . #0#)");
+    match &shared {
+        Value::Pair(pair) => assert!(pair.car().eqv(&pair.cdr().list_to_vec().unwrap()[0])),
+        _ => panic!("not a pair"),
+    }
+    assert_eq!(shared.to_datum().unwrap().to_string(), "((x) (x) x)");
+    assert_eq!(read("#0=(a . #1=(b . #1#))").to_datum().unwrap().to_string(), "(a . #0=(b . #0#))");
+}
+
+#[test]
+fn shared_mutation() {
+    let tail = Value::list(vec![Value::Boolean(true)]);
+    let list = Value::cons(Value::Null, tail.clone());
+    if let Value::Pair(pair) = &tail {
+        pair.set_car(Value::Boolean(false));
+    }
+    assert_eq!(list, Value::list(vec![Value::Null, Value::Boolean(false)]));
+    assert!(!list.eqv(&Value::list(vec![Value::Null, Value::Boolean(false)])));
+
+    if let Value::Pair(pair) = &tail {
+        pair.set_cdr(list.clone());
+    }
+    assert!(list.list_to_vec().is_none());
+}
+
+#[test]
+fn equivalence() {
+    let number = |real| Value::Number(Complex::Real(real));
+    assert!(number(Real::Integer(100)).is_eq(&number(Real::Integer(100))));
+    assert!(!number(Real::Float(1.5)).is_eq(&number(Real::Float(1.5))));
+    assert!(number(Real::Float(0.0)).eqv(&number(Real::Float(0.0))));
+    assert!(!number(Real::Float(0.0)).eqv(&number(Real::Float(-0.0))));
+    assert!(!number(Real::Integer(2)).eqv(&number(Real::Float(2.0))));
+
+    let string = Value::string("a");
+    assert!(string.is_eq(&string.clone()));
+    assert!(!string.is_eq(&Value::string("a")));
+}
+
+#[test]
+fn long_list_drop() {
+    let list = Value::list((0..1_000_000).map(|_| Value::Null).collect::<Vec<_>>());
+    assert_eq!(list.list_to_vec().map(|v| v.len()), Some(1_000_000));
+    drop(list);
+}